@@ -0,0 +1,41 @@
+//tints the sphere per triangle from the textures of TriangleData
+//the primitive index is the position of the drawn triangle in the index buffer, the index texture turns it into a triangle of the sphere
+//needs the SHADER_PRIMITIVE_INDEX feature, TriangleDataPlugin only uses the material when the device has it
+#import bevy_pbr::{
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing},
+    forward_io::{VertexOutput, FragmentOutput},
+}
+
+@group(2) @binding(100) var triangle_index: texture_2d<u32>;
+@group(2) @binding(101) var triangle_data: texture_2d<f32>;
+@group(2) @binding(102) var<uniform> highlight: u32;
+@group(2) @binding(103) var<uniform> highlight_color: vec4<f32>;
+
+//texel holding entry i of a texture filled row by row
+fn texel(i: u32, width: u32) -> vec2<i32> {
+    return vec2<i32>(i32(i % width), i32(i / width));
+}
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+    @builtin(primitive_index) primitive: u32,
+) -> FragmentOutput {
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+
+    let triangle = textureLoad(triangle_index, texel(primitive, textureDimensions(triangle_index).x), 0).r;
+    //the highlight replaces the vertex colors, the tint of the data texture is multiplied with them
+    if triangle == highlight {
+        pbr_input.material.base_color = highlight_color;
+    } else {
+        pbr_input.material.base_color *= textureLoad(triangle_data, texel(triangle, textureDimensions(triangle_data).x), 0);
+    }
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+    var out: FragmentOutput;
+    out.color = apply_pbr_lighting(pbr_input);
+    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    return out;
+}
@@ -0,0 +1,241 @@
+use std::fmt;
+use std::str::FromStr;
+
+use bevy_math::primitives::Triangle3d;
+use bevy_math::Vec3;
+
+use crate::locate::best_fit;
+use crate::geometry::{child_corners, edge_midpoint};
+
+//most base faces of any polyhedron the sphere is grown from, the 20 of the icosahedron
+pub const MAX_FACES: usize = 20;
+
+//stable address of a triangle: the base face it descends from and which child it took at every subdivision
+//addresses don't depend on the order triangles are generated in, so game data can be keyed to them
+//and a triangle keeps its address when the sphere is subdivided further, its children just extend the path
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TriangleAddress {
+    //base face of the polyhedron, 0 to 19 for the icosahedron
+    pub face: u8,
+    //child taken at each subdivision, 0 to 3 in the order subdivide creates them
+    pub path: Vec<u8>,
+    //triangle of a node drawn as several triangles, like the fanned quads of the cube sphere, None for a plain triangle
+    pub piece: Option<u8>,
+}
+
+impl TriangleAddress {
+    pub fn root(face: u8) -> Self {
+        TriangleAddress { face, path: Vec::new(), piece: None }
+    }
+
+    //address of the triangle at the given position among the frequency² triangles frequency_subdivide splits a face into
+    //the path spells the position in base 4 with ceil(log2 frequency) digits, enough for the largest position,
+    //so every triangle of the sphere gets its own address and all of them have the same depth
+    //for a power of 2 frequency the index of the address is the one of the triangle, but the path doesn't follow
+    //midpoint splits, so triangle, from_point and neighbours don't apply to these addresses
+    pub fn frequency(face: u8, position: usize, frequency: usize) -> Self {
+        let depth = frequency.max(1).next_power_of_two().ilog2() as usize;
+        let path = (0..depth).rev().map(|digit| ((position >> (2 * digit)) & 3) as u8).collect();
+        TriangleAddress { face, path, piece: None }
+    }
+
+    //number of subdivisions between the base face and the triangle
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    //the piece of a node has the node itself as its parent
+    pub fn parent(&self) -> Option<TriangleAddress> {
+        let mut path = self.path.clone();
+        if self.piece.is_none() {
+            path.pop()?;
+        }
+        Some(TriangleAddress { face: self.face, path, piece: None })
+    }
+
+    pub fn child(&self, child: u8) -> TriangleAddress {
+        debug_assert!(child < 4 && self.piece.is_none());
+        let mut path = self.path.clone();
+        path.push(child);
+        TriangleAddress { face: self.face, path, piece: None }
+    }
+
+    pub fn piece(&self, piece: u8) -> TriangleAddress {
+        TriangleAddress { piece: Some(piece), ..self.clone() }
+    }
+
+    pub fn children(&self) -> [TriangleAddress; 4] {
+        [self.child(0), self.child(1), self.child(2), self.child(3)]
+    }
+
+    //true if the other triangle lies inside this one, a triangle counts as its own ancestor
+    pub fn is_ancestor_of(&self, other: &TriangleAddress) -> bool {
+        self.face == other.face && other.path.starts_with(&self.path) && (self.piece.is_none() || *self == *other)
+    }
+
+    //position of the triangle in the list produced by subdividing the base faces depth times, the piece isn't part of it
+    pub fn index(&self) -> usize {
+        self.path.iter().fold(self.face as usize, |index, &child| index * 4 + child as usize)
+    }
+
+    //inverse of index, None if the index is past the last triangle of the largest polyhedron
+    pub fn from_index(mut index: usize, depth: usize) -> Option<Self> {
+        let mut path = vec![0; depth];
+        for child in path.iter_mut().rev() {
+            *child = (index % 4) as u8;
+            index /= 4;
+        }
+        if index >= MAX_FACES {
+            return None;
+        }
+        Some(TriangleAddress { face: index as u8, path, piece: None })
+    }
+
+    //address of the triangle at the given depth that contains a point on the sphere
+    pub fn from_point(base: &[Triangle3d], point: Vec3, depth: usize) -> Option<Self> {
+        let face = best_fit(base, point)?;
+        let mut address = TriangleAddress::root(face as u8);
+        let mut triangle = base[face];
+
+        for _ in 0..depth {
+            let children = split_triangle(&triangle);
+            let child = best_fit(&children, point)?;
+            address.path.push(child as u8);
+            triangle = children[child];
+        }
+        Some(address)
+    }
+
+    //corners of the triangle, rebuilt by following the path down from its base face
+    pub fn triangle(&self, base: &[Triangle3d]) -> Option<Triangle3d> {
+        let mut triangle = *base.get(self.face as usize)?;
+        for &child in &self.path {
+            triangle = split_triangle(&triangle)[child as usize];
+        }
+        Some(triangle)
+    }
+
+    //center of the triangle on the unit sphere
+    pub fn to_point(&self, base: &[Triangle3d]) -> Option<Vec3> {
+        Some(self.triangle(base)?.centroid().normalize())
+    }
+
+    //triangles of the same depth across the edges ab, bc and ca
+    pub fn neighbours(&self, base: &[Triangle3d]) -> Option<[TriangleAddress; 3]> {
+        let triangle = self.triangle(base)?;
+        let [a, b, c] = triangle.vertices;
+        let mut neighbours = [(); 3].map(|_| TriangleAddress::default());
+        for (i, (p, q, opposite)) in [(a, b, c), (b, c, a), (c, a, b)].into_iter().enumerate() {
+            //step just past the middle of the edge, away from the opposite corner
+            let mid = edge_midpoint(p, q);
+            let point = mid + (mid - opposite) * 0.01;
+            neighbours[i] = TriangleAddress::from_point(base, point, self.depth())?;
+        }
+        Some(neighbours)
+    }
+}
+
+fn split_triangle(triangle: &Triangle3d) -> [Triangle3d; 4] {
+    let [a, b, c] = triangle.vertices;
+    child_corners([a, b, c], [edge_midpoint(a, b), edge_midpoint(b, c), edge_midpoint(c, a)])
+        .map(|corners| Triangle3d::new(corners[0], corners[1], corners[2]))
+}
+
+//written as the face followed by the path, for example 7-0312, or just 7 for a base face
+//a piece follows after a dot, 7-0312.2
+impl fmt::Display for TriangleAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.face)?;
+        if !self.path.is_empty() {
+            write!(f, "-")?;
+            for child in &self.path {
+                write!(f, "{}", child)?;
+            }
+        }
+        if let Some(piece) = self.piece {
+            write!(f, ".{}", piece)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAddressError(pub String);
+
+impl fmt::Display for ParseAddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid triangle address: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseAddressError {}
+
+impl FromStr for TriangleAddress {
+    type Err = ParseAddressError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (node, piece) = match s.split_once('.') {
+            Some((node, piece)) if !piece.is_empty() && piece.bytes().all(|byte| byte.is_ascii_digit()) => {
+                (node, Some(piece.parse::<u8>().map_err(|_| ParseAddressError(s.to_string()))?))
+            }
+            Some(_) => return Err(ParseAddressError(s.to_string())),
+            None => (s, None),
+        };
+        let (face, path) = node.split_once('-').unwrap_or((node, ""));
+        //u8 parsing alone would also take a sign
+        if face.is_empty() || !face.bytes().all(|byte| byte.is_ascii_digit()) || (node.contains('-') && path.is_empty()) {
+            return Err(ParseAddressError(s.to_string()));
+        }
+        let face = face.parse::<u8>().ok().filter(|&face| (face as usize) < MAX_FACES).ok_or_else(|| ParseAddressError(s.to_string()))?;
+        let path = path
+            .chars()
+            .map(|child| match child.to_digit(4) {
+                Some(child) => Ok(child as u8),
+                None => Err(ParseAddressError(s.to_string())),
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        Ok(TriangleAddress { face, path, piece })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adjacency::TriangleAdjacency;
+    use crate::geometry::{icosahedron, uniform_levels, Polyhedron};
+
+    #[test]
+    fn addresses_round_trip() {
+        for text in ["7", "7-0312", "19-3", "0-00", "5-01.3", "2.0"] {
+            let address: TriangleAddress = text.parse().unwrap();
+            assert_eq!(address.to_string(), text);
+        }
+        for text in ["", "-", "7-", "7-4", "+3-01", "255-0123", "20", "a-01", "3-0 1", "3-01.", "3-01.+1", "3.1.2"] {
+            assert!(text.parse::<TriangleAddress>().is_err(), "{} was accepted", text);
+        }
+        assert_eq!(TriangleAddress::from_index(20 * 16, 2), None);
+    }
+
+    #[test]
+    fn addresses_find_their_triangles_and_neighbours() {
+        let base: Vec<Triangle3d> = icosahedron().1.iter().map(|triangle| triangle.triangle).collect();
+        let (vertices, triangles) = uniform_levels(Polyhedron::Icosahedron, 2).pop().unwrap();
+        let adjacency = TriangleAdjacency::new(vertices.len(), &triangles);
+
+        for triangle in &triangles {
+            let address = &triangle.address;
+            assert_eq!(address.index(), triangle.index);
+            assert_eq!(TriangleAddress::from_index(triangle.index, 2).as_ref(), Some(address));
+
+            let point = address.to_point(&base).unwrap();
+            assert!(point.distance(triangle.triangle.centroid().normalize()) < 1e-5);
+            assert_eq!(TriangleAddress::from_point(&base, point, 2).as_ref(), Some(address));
+
+            let neighbours = address.neighbours(&base).unwrap();
+            for (i, neighbour) in neighbours.iter().enumerate() {
+                let across = adjacency.edge_neighbours[triangle.index][i].unwrap();
+                assert_eq!(neighbour, &triangles[across].address);
+            }
+        }
+    }
+}
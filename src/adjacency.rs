@@ -0,0 +1,182 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+use crate::geometry::Triangle;
+
+//neighbours of every triangle, built once whenever the triangles of the sphere change
+#[derive(Clone, Default)]
+pub struct TriangleAdjacency {
+    //triangle across each of the edges ab, bc and ca
+    //None where the edge is not shared exactly, which happens along lod seams where the neighbour is split
+    pub edge_neighbours: Vec<[Option<usize>; 3]>,
+    //every other triangle sharing at least one corner, this includes the edge neighbours
+    pub vertex_neighbours: Vec<Vec<usize>>,
+}
+
+impl TriangleAdjacency {
+    pub fn new(vertex_count: usize, triangles: &[Triangle]) -> Self {
+        let mut vertex_triangles: Vec<Vec<usize>> = vec![Vec::new(); vertex_count];
+        let mut edges: HashMap<(u32, u32), usize> = HashMap::new();
+        let mut edge_neighbours = vec![[None; 3]; triangles.len()];
+
+        for (t, triangle) in triangles.iter().enumerate() {
+            for i in 0..3 {
+                let a = triangle.corners[i];
+                let b = triangle.corners[(i + 1) % 3];
+                vertex_triangles[a as usize].push(t);
+
+                //the first triangle to see an edge waits for the second one to link them up
+                match edges.remove(&(a.min(b), a.max(b))) {
+                    Some(other) => {
+                        //the other triangle runs along the edge the other way round, unless the winding is broken
+                        let back = (0..3).find(|&j| {
+                            let corners = triangles[other].corners;
+                            corners[j] == b && corners[(j + 1) % 3] == a
+                        });
+                        debug_assert!(back.is_some(), "triangles {} and {} run along their shared edge the same way, one of them is wound inwards", other, t);
+                        if let Some(j) = back {
+                            edge_neighbours[t][i] = Some(other);
+                            edge_neighbours[other][j] = Some(t);
+                        }
+                    }
+                    None => {
+                        edges.insert((a.min(b), a.max(b)), t);
+                    }
+                }
+            }
+        }
+
+        let vertex_neighbours = triangles
+            .iter()
+            .enumerate()
+            .map(|(t, triangle)| {
+                let mut neighbours: Vec<usize> = triangle
+                    .corners
+                    .iter()
+                    .flat_map(|&corner| vertex_triangles[corner as usize].iter().copied())
+                    .filter(|&other| other != t)
+                    .collect();
+                neighbours.sort_unstable();
+                neighbours.dedup();
+                neighbours
+            })
+            .collect();
+
+        TriangleAdjacency {
+            edge_neighbours,
+            vertex_neighbours,
+        }
+    }
+
+    //ring distance of every triangle to the closest of the sources, triangles sharing a corner are one ring apart
+    pub fn ring_distances(&self, sources: &[usize]) -> Vec<i32> {
+        ring_distances(&self.vertex_neighbours, sources)
+    }
+}
+
+//ring distance of every entry of the neighbour lists to the closest of the sources, found with a single breadth first search
+//unreachable entries get -1, see rings_within for a search that stops early
+pub fn ring_distances(neighbours: &[Vec<usize>], sources: &[usize]) -> Vec<i32> {
+    let mut distances = vec![-1; neighbours.len()];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+
+    for &source in sources {
+        if source < distances.len() && distances[source] < 0 {
+            distances[source] = 0;
+            queue.push_back(source);
+        }
+    }
+
+    while let Some(current) = queue.pop_front() {
+        for &neighbour in &neighbours[current] {
+            if distances[neighbour] < 0 {
+                distances[neighbour] = distances[current] + 1;
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    distances
+}
+
+//rings around the sources out to the given ring only, as pairs of index and ring distance
+//works on any neighbour lists, the vertex neighbours of the triangles or the neighbours of the cells
+//the search stops at the last ring, so its cost depends on the reach rather than the size of the sphere
+pub fn rings_within(neighbours: &[Vec<usize>], sources: &[usize], reach: i32) -> Vec<(usize, i32)> {
+    let mut found: HashMap<usize, i32> = HashMap::new();
+    let mut queue: VecDeque<usize> = VecDeque::new();
+
+    for &source in sources {
+        if source < neighbours.len() && reach >= 0 {
+            if let Entry::Vacant(entry) = found.entry(source) {
+                entry.insert(0);
+                queue.push_back(source);
+            }
+        }
+    }
+
+    let mut rings = Vec::new();
+    while let Some(current) = queue.pop_front() {
+        let distance = found[&current];
+        rings.push((current, distance));
+        if distance == reach {
+            continue;
+        }
+        for &neighbour in &neighbours[current] {
+            if let Entry::Vacant(entry) = found.entry(neighbour) {
+                entry.insert(distance + 1);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+    rings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::geodesic_triangles;
+
+    #[test]
+    fn geodesic_adjacency_is_symmetric() {
+        let (vertices, triangles) = geodesic_triangles(2);
+        let adjacency = TriangleAdjacency::new(vertices.len(), &triangles);
+
+        let mut valence = vec![0; vertices.len()];
+        for triangle in &triangles {
+            for corner in triangle.corners {
+                valence[corner as usize] += 1;
+            }
+        }
+        for (t, triangle) in triangles.iter().enumerate() {
+            for (i, neighbour) in adjacency.edge_neighbours[t].iter().enumerate() {
+                let neighbour = neighbour.expect("every edge of a closed sphere is shared");
+                assert!(adjacency.edge_neighbours[neighbour].contains(&Some(t)));
+                let (a, b) = (triangle.corners[i], triangle.corners[(i + 1) % 3]);
+                assert!(triangles[neighbour].corners.contains(&a) && triangles[neighbour].corners.contains(&b));
+            }
+            //the triangles around the corners, without the triangle itself and counting the edge neighbours once
+            let expected: usize = triangle.corners.iter().map(|&corner| valence[corner as usize]).sum::<usize>() - 6;
+            assert_eq!(adjacency.vertex_neighbours[t].len(), expected);
+        }
+        //the 12 pentagon corners of the icosahedron have 5 triangles each, which see one neighbour less
+        assert_eq!(adjacency.vertex_neighbours.iter().filter(|neighbours| neighbours.len() == 11).count(), 60);
+        assert!(adjacency.vertex_neighbours.iter().all(|neighbours| neighbours.len() == 11 || neighbours.len() == 12));
+
+        //ring by ring, every triangle sharing a corner with the previous ring
+        let sources = [0, 200];
+        let mut expected = vec![-1; triangles.len()];
+        let mut ring: Vec<usize> = sources.to_vec();
+        let mut distance = 0;
+        while !ring.is_empty() {
+            for &t in &ring {
+                expected[t] = distance;
+            }
+            let corners: Vec<u32> = ring.iter().flat_map(|&t| triangles[t].corners).collect();
+            ring = (0..triangles.len())
+                .filter(|&t| expected[t] < 0 && triangles[t].corners.iter().any(|corner| corners.contains(corner)))
+                .collect();
+            distance += 1;
+        }
+        assert_eq!(adjacency.ring_distances(&sources), expected);
+    }
+}
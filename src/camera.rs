@@ -0,0 +1,142 @@
+use bevy::prelude::*;
+
+use crate::character::CharacterState;
+use crate::height::Heightmap;
+use crate::LodCamera;
+
+//how the LodCamera is placed
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum CameraMode {
+    //looks at the center of the planet from a distance
+    #[default]
+    Orbit,
+    //follows the character from behind and above
+    Chase,
+}
+
+#[derive(Resource, Clone, Copy, PartialEq, Debug)]
+pub struct CameraSettings {
+    pub mode: CameraMode,
+    //orientation of the orbit camera, it sits on its own +z axis looking at the center of the planet
+    pub orbit_rotation: Quat,
+    //distance of the orbit camera from the center of the planet
+    pub orbit_distance: f32,
+    //distance of the chase camera behind the character
+    pub chase_distance: f32,
+    //height of the chase camera above the character, as a fraction of chase_distance
+    pub chase_height: f32,
+    //closest the camera gets to the terrain in either mode
+    pub min_altitude: f32,
+    //how quickly the camera catches up with where it should be, per second, higher is stiffer
+    pub smoothing: f32,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        CameraSettings {
+            mode: CameraMode::Orbit,
+            orbit_rotation: Quat::IDENTITY,
+            orbit_distance: 4.0,
+            chase_distance: 0.15,
+            chase_height: 0.5,
+            min_altitude: 0.02,
+            smoothing: 5.0,
+        }
+    }
+}
+
+impl CameraSettings {
+    //arcball rotation of the orbit camera around the planet for a drag across the screen, in radians
+    //the drag turns about the axis perpendicular to it in the view, so there is no fixed yaw axis to lock up
+    //the planet follows the drag like it is being grabbed, the camera moves the opposite way
+    pub fn orbit(&mut self, drag: Vec2) {
+        let Some(axis) = Vec3::new(drag.y, drag.x, 0.0).try_normalize() else {
+            return;
+        };
+        self.orbit_rotation = (self.orbit_rotation * Quat::from_axis_angle(axis, -drag.length())).normalize();
+    }
+
+    //moves the camera of the current mode closer for positive amounts and further away for negative ones
+    pub fn zoom(&mut self, amount: f32) {
+        let scale = (1.0 - amount).clamp(0.5, 2.0);
+        match self.mode {
+            CameraMode::Orbit => self.orbit_distance = (self.orbit_distance * scale).clamp(1.0 + self.min_altitude, 20.0),
+            CameraMode::Chase => self.chase_distance = (self.chase_distance * scale).clamp(0.02, 2.0),
+        }
+    }
+}
+
+//pushes a point out until it is at least min_altitude above the terrain under it
+pub fn keep_above_terrain(point: Vec3, heightmap: &Heightmap, min_altitude: f32) -> Vec3 {
+    let Some(direction) = point.try_normalize() else {
+        return point;
+    };
+    let floor = 1.0 + heightmap.height(direction) + min_altitude;
+    if point.length() < floor {
+        direction * floor
+    } else {
+        point
+    }
+}
+
+//where the camera should be this frame, before smoothing
+pub fn camera_target(settings: &CameraSettings, character_state: &CharacterState) -> Transform {
+    match settings.mode {
+        CameraMode::Orbit => Transform::from_translation(settings.orbit_rotation.mul_vec3(Vec3::Z * settings.orbit_distance)).with_rotation(settings.orbit_rotation),
+        CameraMode::Chase => {
+            let character = character_state.visual_transform.translation;
+            let up = character_state.up;
+            let position = character - character_state.forward * settings.chase_distance + up * settings.chase_distance * settings.chase_height;
+            Transform::from_translation(position).looking_at(character, up)
+        }
+    }
+}
+
+//eases the LodCamera towards the target of the current mode, so switching modes flies over instead of jumping
+//runs after the character moved, the camera never ends up closer to the terrain than min_altitude
+pub fn update_camera(
+    settings: Res<CameraSettings>,
+    character_state: Res<CharacterState>,
+    heightmap: Res<Heightmap>,
+    time: Res<Time>,
+    mut camera_query: Query<&mut Transform, With<LodCamera>>,
+) {
+    let target = camera_target(&settings, &character_state);
+    let t = 1.0 - (-settings.smoothing * time.delta_seconds()).exp();
+
+    for mut transform in &mut camera_query {
+        let position = transform.translation.lerp(target.translation, t);
+        transform.translation = keep_above_terrain(position, &heightmap, settings.min_altitude);
+        transform.rotation = transform.rotation.slerp(target.rotation, t);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::height::FractalNoise;
+
+    #[test]
+    fn zoom_never_reaches_the_terrain() {
+        let heightmap = Heightmap::Ridged(FractalNoise::default());
+        let mut settings = CameraSettings::default();
+        for _ in 0..100 {
+            settings.zoom(0.5);
+        }
+        let position = keep_above_terrain(Vec3::new(0.0, 0.0, settings.orbit_distance), &heightmap, settings.min_altitude);
+        assert!(position.length() >= heightmap.displace(position.normalize()).length() + settings.min_altitude - 1e-5);
+        assert!(position.length() > settings.orbit_distance);
+    }
+
+    #[test]
+    fn orbit_keeps_looking_at_the_planet() {
+        let mut settings = CameraSettings::default();
+        let character_state = CharacterState::default();
+        for i in 0..200 {
+            settings.orbit(Vec2::new((i as f32 * 0.3).sin(), (i as f32 * 0.7).cos()) * 0.05);
+            let target = camera_target(&settings, &character_state);
+            assert!((target.translation.length() - settings.orbit_distance).abs() < 1e-4);
+            assert!(target.forward().dot(-target.translation.normalize()) > 0.9999);
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use bevy_math::Vec3;
+
+use crate::adjacency::ring_distances;
+use crate::geometry::Triangle;
+use crate::locate::polygon_containment;
+
+//dual of the triangle mesh, one cell per shared vertex with a corner at the center of every triangle around it
+//on a geodesic sphere the 12 vertices of the icosahedron become pentagons and every other vertex a hexagon
+//vertices on lod seams lie on the edge of a coarser triangle, their cells are left open on that side
+#[derive(Clone, Default)]
+pub struct CellGrid {
+    //triangles around every cell, ordered counter clockwise seen from outside the sphere
+    pub triangles: Vec<Vec<usize>>,
+    //corners of every cell on the unit sphere, the centers of the triangles above in the same order
+    pub corners: Vec<Vec<Vec3>>,
+    //cells sharing an edge of the triangle mesh with every cell, which are the cells sharing a side
+    pub neighbours: Vec<Vec<usize>>,
+}
+
+impl CellGrid {
+    pub fn new(vertices: &[Vec3], triangles: &[Triangle]) -> Self {
+        let mut cell_triangles: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+        let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); vertices.len()];
+
+        for (t, triangle) in triangles.iter().enumerate() {
+            for i in 0..3 {
+                let corner = triangle.corners[i] as usize;
+                cell_triangles[corner].push(t);
+                neighbours[corner].push(triangle.corners[(i + 1) % 3] as usize);
+                neighbours[corner].push(triangle.corners[(i + 2) % 3] as usize);
+            }
+        }
+
+        let centers: Vec<Vec3> = triangles
+            .iter()
+            .map(|triangle| triangle.corners.iter().map(|&corner| vertices[corner as usize]).sum::<Vec3>().normalize())
+            .collect();
+
+        //walk around every vertex by the angle of the triangle centers in its tangent plane
+        for (cell, around) in cell_triangles.iter_mut().enumerate() {
+            let normal = vertices[cell].normalize();
+            let (x, y) = normal.any_orthonormal_pair();
+            let angle = |t: usize| {
+                let offset = centers[t] - normal;
+                offset.dot(y).atan2(offset.dot(x))
+            };
+            around.sort_by(|&a, &b| angle(a).total_cmp(&angle(b)));
+        }
+        for neighbours in &mut neighbours {
+            neighbours.sort_unstable();
+            neighbours.dedup();
+        }
+
+        let corners = cell_triangles.iter().map(|around| around.iter().map(|&t| centers[t]).collect()).collect();
+
+        CellGrid {
+            triangles: cell_triangles,
+            corners,
+            neighbours,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    //cell containing a point inside a triangle, one of the cells of its corners
+    //the one whose polygon contains the point, or comes closest to it for points right on a border
+    pub fn cell_in_triangle(&self, triangle: &Triangle, point: Vec3) -> usize {
+        let point = point.normalize();
+        triangle
+            .corners
+            .iter()
+            .map(|&corner| corner as usize)
+            .max_by(|&a, &b| polygon_containment(&self.corners[a], point).total_cmp(&polygon_containment(&self.corners[b], point)))
+            .unwrap_or(0)
+    }
+
+    //ring distance of every cell to the closest of the sources, cells sharing a side are one ring apart
+    pub fn ring_distances(&self, sources: &[usize]) -> Vec<i32> {
+        ring_distances(&self.neighbours, sources)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{icosahedron, subdivide};
+
+    #[test]
+    fn geodesic_dual_is_pentagons_and_hexagons() {
+        let (mut vertices, mut triangles) = icosahedron();
+        for _ in 0..3 {
+            (vertices, triangles) = subdivide(vertices, triangles);
+        }
+        let cells = CellGrid::new(&vertices, &triangles);
+
+        assert_eq!(cells.len(), vertices.len());
+        assert_eq!(cells.triangles.iter().filter(|around| around.len() == 5).count(), 12);
+        for (cell, around) in cells.triangles.iter().enumerate() {
+            assert!(around.len() == 5 || around.len() == 6);
+            assert_eq!(cells.neighbours[cell].len(), around.len());
+            for &neighbour in &cells.neighbours[cell] {
+                assert!(cells.neighbours[neighbour].contains(&cell));
+            }
+            //consecutive corners belong to triangles sharing an edge, so the polygon is not twisted
+            for (i, &t) in around.iter().enumerate() {
+                let next = &triangles[around[(i + 1) % around.len()]];
+                let shared = triangles[t].corners.iter().filter(|corner| next.corners.contains(corner)).count();
+                assert_eq!(shared, 2);
+            }
+        }
+
+        assert_eq!(cells.ring_distances(&[0])[0], 0);
+        assert!(cells.ring_distances(&[0]).iter().all(|&distance| distance >= 0));
+    }
+    #[test]
+    fn points_next_to_a_cell_border_land_in_the_right_cell() {
+        let (mut vertices, mut triangles) = icosahedron();
+        for _ in 0..3 {
+            (vertices, triangles) = subdivide(vertices, triangles);
+        }
+        let cells = CellGrid::new(&vertices, &triangles);
+
+        //cells of neighbouring vertices meet along the arc between the centers of the two triangles sharing their edge
+        //step off it by a little to either side, where the closest of the vertices is often the wrong cell
+        let mut closest_wrong = 0;
+        for triangle in &triangles {
+            for (i, j) in [(0, 1), (1, 2), (2, 0)] {
+                let (p, q) = (triangle.corners[i] as usize, triangle.corners[j] as usize);
+                let shared: Vec<usize> = cells.triangles[p].iter().copied().filter(|t| cells.triangles[q].contains(t)).collect();
+                let [first, second] = [shared[0], shared[1]].map(|t| triangles[t].corners.iter().map(|&corner| vertices[corner as usize]).sum::<Vec3>().normalize());
+                let border = first.lerp(second, 0.3);
+                let across = (vertices[q] - vertices[p]) * 0.01;
+                for (point, cell) in [(border - across, p), (border + across, q)] {
+                    assert_eq!(cells.cell_in_triangle(triangle, point), cell);
+                    let closest = if vertices[p].distance(point) < vertices[q].distance(point) { p } else { q };
+                    closest_wrong += usize::from(closest != cell);
+                }
+            }
+        }
+        assert!(closest_wrong > 0);
+    }
+}
@@ -0,0 +1,260 @@
+use bevy::prelude::*;
+
+use crate::address::TriangleAddress;
+use crate::geometry::Triangle;
+use crate::height::Heightmap;
+use crate::input::CharacterInput;
+use crate::morph::Geomorph;
+use crate::sphere::SphereState;
+
+//marks the entity moving over the sphere
+#[derive(Component)]
+pub struct Character;
+
+#[derive(Resource, Clone)]
+pub struct CharacterState {
+    //position on the terrain, normalized it is the position on the unit sphere
+    pub center: Vec3,
+    //pose the character is drawn with, standing on the rendered triangle under it
+    pub visual_transform: Transform,
+    //heading, tangent to the unit sphere at the center
+    pub forward: Vec3,
+    //local up vector, the normal of the terrain under the character
+    pub up: Vec3,
+    //right direction, forward crossed with the normalized center
+    pub right: Vec3,
+    //walking speed along the great circle of the heading
+    pub move_speed: MoveSpeed,
+    //turning speed in radians per second
+    pub turn_speed: f32,
+    //height above the terrain, 0 unless jumping
+    pub altitude: f32,
+    //speed away from the terrain while jumping
+    pub vertical_speed: f32,
+    //vertical speed at the start of a jump, in radii per second
+    pub jump_speed: f32,
+    //id of the closest triangle
+    pub current_triangle_id: usize,
+    //current triangle
+    pub current_traingle: Triangle,
+    //id of the cell under the character, the shared vertex closest to it
+    pub current_cell_id: usize,
+}
+
+//how fast the character walks over the sphere
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MoveSpeed {
+    //angle swept around the center of the sphere
+    RadiansPerSecond(f32),
+    //distance over the surface, on a planet with the given radius in metres
+    MetresPerSecond { speed: f32, radius: f32 },
+}
+
+impl MoveSpeed {
+    pub fn radians_per_second(&self) -> f32 {
+        match *self {
+            MoveSpeed::RadiansPerSecond(speed) => speed,
+            MoveSpeed::MetresPerSecond { speed, radius } => speed / radius,
+        }
+    }
+}
+
+impl Default for CharacterState {
+    fn default() -> Self {
+        CharacterState { 
+            center: Vec3::Z,
+            visual_transform: Transform::from_xyz(0.0, 0.0, 0.0),
+            current_triangle_id: 0, 
+            current_traingle: Triangle {index: 0, triangle: Triangle3d::new(Vec3::new(0.0,0.0,0.0), Vec3::new(0.0,0.0,0.0), Vec3::new(0.0,0.0,0.0)), corners: [0, 0, 0], address: TriangleAddress::default()},
+            current_cell_id: 0,
+            forward: Vec3::Y,
+            right: Vec3::Y.cross(Vec3::Z),
+            up: Vec3::Z,
+            move_speed: MoveSpeed::RadiansPerSecond(1.0),
+            turn_speed: 5.0,
+            altitude: 0.0,
+            vertical_speed: 0.0,
+            jump_speed: 0.6,
+        }
+    }
+}
+
+//character (cube for now)
+pub fn spawn_character(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.spawn((
+        PbrBundle {
+            //origin at the bottom face, so the cube stands on the surface instead of sinking into it halfway
+            mesh: meshes.add(Mesh::from(Cuboid::new(0.02, 0.02, 0.02)).translated_by(Vec3::Y * 0.01)),
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgb(0.0, 0.8, 0.2),
+                ..Default::default()
+            }),
+            transform: Transform::from_xyz(0.0, 0.0, 1.0),
+            ..Default::default()
+        },
+        Character,
+    ));
+}
+
+//point on the terrain in the given direction from the center and the terrain normal there
+pub fn surface_point(direction: Vec3, heightmap: &Heightmap) -> (Vec3, Vec3) {
+    let direction = direction.normalize();
+    (heightmap.displace(direction), heightmap.normal(direction))
+}
+
+//pull of the planet on a jumping character, in radii per second squared
+const GRAVITY: f32 = 3.0;
+
+//turns the heading right by turn radians about the vertical of the unit sphere, then walks along a great circle,
+//walk.y radians forward and walk.x radians to the right
+//both steps are rotations that move the direction and heading together, so the heading is kept exactly
+//and the only correction needed is for rounding, which a single projection removes
+pub fn geodesic_step(direction: Vec3, forward: Vec3, walk: Vec2, turn: f32) -> (Vec3, Vec3) {
+    let direction = direction.normalize();
+    let forward = (forward - direction * direction.dot(forward)).try_normalize().unwrap_or(direction.any_orthonormal_vector());
+    let forward = Quat::from_axis_angle(direction, -turn).mul_vec3(forward);
+
+    //rotating about up × step moves the direction towards the step
+    let step = forward * walk.y + forward.cross(direction) * walk.x;
+    let Some(axis) = direction.cross(step).try_normalize() else {
+        return (direction, forward);
+    };
+    let rotation = Quat::from_axis_angle(axis, walk.length());
+    let direction = rotation.mul_vec3(direction).normalize();
+    let forward = rotation.mul_vec3(forward);
+    (direction, (forward - direction * direction.dot(forward)).normalize())
+}
+
+pub fn handle_character_movement(
+    mut character_state: ResMut<CharacterState>,
+    mut character_query: Query<(&Character, &mut Transform)>,
+    sphere_state: Res<SphereState>,
+    heightmap: Res<Heightmap>,
+    geomorph: Res<Geomorph>,
+    input: Res<CharacterInput>,
+    time: Res<Time>,
+) {
+    //find the triangle under the character and store its id
+    let center = character_state.center;
+    if let Some(closest_triangle_id) = sphere_state.locate(center) {
+        character_state.current_triangle_id = closest_triangle_id;
+        character_state.current_traingle = sphere_state.triangles[closest_triangle_id].clone();
+        character_state.current_cell_id = sphere_state.cells.cell_in_triangle(&sphere_state.triangles[closest_triangle_id], center);
+    }
+    let dt = time.delta_seconds();
+
+    //jump when standing on the ground, then fall back
+    if input.jump && character_state.altitude <= 0.0 {
+        character_state.vertical_speed = character_state.jump_speed;
+    }
+    character_state.vertical_speed -= GRAVITY * dt;
+    character_state.altitude = (character_state.altitude + character_state.vertical_speed * dt).max(0.0);
+    if character_state.altitude <= 0.0 {
+        character_state.vertical_speed = 0.0;
+    }
+
+    for (_, mut transform) in &mut character_query {
+
+        //turn and walk on the unit sphere, then stand on the terrain there
+        //the planet stays put while the camera moves around it, so the world space state carries over between frames
+        let walk = Vec2::new(input.strafe, input.forward) * character_state.move_speed.radians_per_second() * dt;
+        let turn = character_state.turn_speed * input.turn * dt;
+        let (direction, forward) = geodesic_step(character_state.center, character_state.forward, walk, turn);
+        character_state.forward = forward;
+        character_state.right = forward.cross(direction);
+        (character_state.center, character_state.up) = surface_point(direction, &heightmap);
+
+        //the model is drawn on the mesh rather than the terrain
+        *transform = calculate_visual_transform(&character_state, &sphere_state, &geomorph, &heightmap);
+        character_state.visual_transform = *transform;
+    }
+}
+
+//pose of the character standing on the rendered mesh, the movement itself happens on the smooth terrain
+//the flat facets of the mesh cut below the terrain between the vertices, so the character is moved along the ray
+//from the center onto the facet under it and tilted with the facet
+pub fn calculate_visual_transform(character_state: &CharacterState, sphere_state: &SphereState, geomorph: &Geomorph, heightmap: &Heightmap) -> Transform {
+    let direction = character_state.center.normalize();
+
+    //facet under the character
+    let (position, normal) = sphere_state.rendered_surface(direction, geomorph, heightmap).unwrap_or((character_state.center, character_state.up));
+
+    //calc forward vector
+    let projected_forward = (character_state.forward - normal * normal.dot(character_state.forward)).normalize();
+
+    //calc projected right vector
+    let projected_right = normal.cross(projected_forward).normalize();
+
+    //creat rotation matrix
+    let rotation = Quat::from_mat3(&Mat3::from_cols(projected_right, normal, projected_forward));
+
+    let mut transform = Transform::IDENTITY;
+    transform.rotation = rotation;
+    transform.translation = position + direction * character_state.altitude;
+    transform
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::height::tests::directions;
+    use crate::height::FractalNoise;
+    use crate::locate::containment;
+    use crate::lod::tests::settings;
+
+    #[test]
+    fn walking_a_great_circle_keeps_the_heading() {
+        let start = Vec3::new(0.3, -0.5, 0.8).normalize();
+        let heading = start.any_orthonormal_vector();
+        let axis = start.cross(heading);
+        let (mut direction, mut forward) = (start, heading);
+        //a full turn around the sphere in small steps
+        for _ in 0..1000 {
+            (direction, forward) = geodesic_step(direction, forward, Vec2::new(0.0, std::f32::consts::TAU / 1000.0), 0.0);
+            assert!(direction.dot(axis).abs() < 1e-4);
+            assert!(direction.dot(forward).abs() < 1e-5);
+            assert!((forward.length() - 1.0).abs() < 1e-5);
+            assert!((direction.cross(forward).dot(axis) - 1.0).abs() < 1e-4);
+        }
+        assert!(direction.distance(start) < 1e-3);
+        assert!(forward.distance(heading) < 1e-3);
+    }
+
+    //radius of the rendered mesh along a direction, found by checking every triangle of the mesh
+    fn mesh_radius(sphere_state: &SphereState, heightmap: &Heightmap, direction: Vec3) -> f32 {
+        sphere_state.indices.chunks_exact(3)
+            .map(|corners| corners.iter().map(|&corner| heightmap.displace(sphere_state.vertices[corner as usize])).collect::<Vec<Vec3>>())
+            .filter(|corners| containment(&Triangle3d::new(corners[0], corners[1], corners[2]), direction) >= -1e-6)
+            .map(|corners| {
+                let normal = (corners[1] - corners[0]).cross(corners[2] - corners[0]).normalize();
+                corners[0].dot(normal) / direction.dot(normal)
+            })
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn character_never_ends_up_below_the_mesh() {
+        let heightmap = Heightmap::Ridged(FractalNoise { amplitude: 0.1, ..default() });
+        let geomorph = Geomorph::instant();
+
+        //a coarse uniform sphere, and a lod sphere with fanned seams around the focus
+        let (uniform, lod) = (SphereState::uniform(1), SphereState::lod(1, &settings(), &[Vec3::Z]));
+
+        for sphere_state in [uniform, lod] {
+            for direction in directions(500) {
+                let (center, up) = surface_point(direction, &heightmap);
+                let character_state = CharacterState { center, up, forward: up.any_orthonormal_vector(), ..default() };
+                let transform = calculate_visual_transform(&character_state, &sphere_state, &geomorph, &heightmap);
+
+                let radius = mesh_radius(&sphere_state, &heightmap, direction);
+                assert!(radius > 0.8);
+                assert!(transform.translation.length() >= radius - 1e-4, "{} is below the mesh at {}", transform.translation.length(), radius);
+                assert!(transform.translation.normalize().dot(direction) > 0.9999);
+            }
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::adjacency::rings_within;
+use crate::character::CharacterState;
+use crate::sphere::{insert_vertex_values, NormalMode, SphereState};
+
+//what the colors show the distance of
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum ColorMode {
+    //rings of triangles around the triangle under the character
+    #[default]
+    Triangles,
+    //rings of hexagonal cells around the cell under the character
+    Cells,
+}
+
+//value of every shared vertex the ramp turns into a color
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ColorMetric {
+    //rings of triangles or cells to the character, depending on the ColorMode
+    #[default]
+    RingDistance,
+    //angle between the vertex and the character around the center of the sphere, in radians
+    GreatCircle,
+    //deepest subdivision level of the triangles using the vertex, shows the lod bands
+    LodLevel,
+}
+
+//how colors are picked between two stops
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Interpolation {
+    //blends from the color of one stop to the next
+    Linear,
+    //keeps the color of a stop until the next one is reached
+    #[default]
+    Stepped,
+}
+
+//turns the value of a metric into a color, replaced at runtime to change what the overlay shows
+//the defaults can be replaced by a ron file read with load_ron, see assets/colors.ron
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColorRamp {
+    pub metric: ColorMetric,
+    pub interpolation: Interpolation,
+    //value of the metric and the linear rgba color there, sorted by value
+    //values before the first stop get its color, values after the last one the last color
+    #[serde(deserialize_with = "sorted_stops")]
+    pub stops: Vec<(f32, [f32; 4])>,
+}
+
+//the stops may be written in any order, they are sorted here
+fn sorted_stops<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(f32, [f32; 4])>, D::Error> {
+    let mut stops: Vec<(f32, [f32; 4])> = Vec::deserialize(deserializer)?;
+    if stops.is_empty() {
+        return Err(D::Error::custom("no stops"));
+    }
+    if stops.iter().any(|(value, _)| value.is_nan()) {
+        return Err(D::Error::custom("stop value is not a number"));
+    }
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok(stops)
+}
+
+impl Default for ColorRamp {
+    //red under the character, green next to it and blue everywhere else
+    fn default() -> Self {
+        ColorRamp {
+            metric: ColorMetric::RingDistance,
+            interpolation: Interpolation::Stepped,
+            stops: vec![
+                (0.0, [1.0, 0.0, 0.0, 1.0]),
+                (1.0, [0.0, 1.0, 0.0, 1.0]),
+                (2.0, [0.0, 0.0, 1.0, 1.0]),
+            ],
+        }
+    }
+}
+
+impl ColorRamp {
+    //color of a value of the metric, white without stops
+    pub fn sample(&self, value: f32) -> [f32; 4] {
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return [1.0; 4];
+        };
+        //index of the first stop past the value
+        let next = self.stops.partition_point(|(stop, _)| *stop <= value);
+        if next == 0 {
+            return first.1;
+        }
+        if next == self.stops.len() {
+            return last.1;
+        }
+        let (from, to) = (self.stops[next - 1], self.stops[next]);
+        match self.interpolation {
+            Interpolation::Stepped => from.1,
+            Interpolation::Linear => {
+                let t = (value - from.0) / (to.0 - from.0);
+                Vec4::from(from.1).lerp(Vec4::from(to.1), t).into()
+            }
+        }
+    }
+}
+
+//name of the triangle layer drawn instead of the ramp, the layer needs a color function, see TriangleLayer::with_colors
+//the ramp is drawn while there is no such layer
+#[derive(Resource, Clone, Default, PartialEq, Eq, Debug)]
+pub struct ColorLayer(pub Option<String>);
+
+//resources choosing what the colors of the sphere show
+#[derive(SystemParam)]
+pub struct ColorSettings<'w> {
+    pub mode: Res<'w, ColorMode>,
+    pub ramp: Res<'w, ColorRamp>,
+    pub layer: Res<'w, ColorLayer>,
+}
+
+//colors last written to the mesh, so they are only measured again when something they depend on changed
+//beyond the last stop of the ramp every vertex has the same color, so a move of the character only touches the vertices near it
+#[derive(Default)]
+pub struct ColorCache {
+    //color of every shared vertex as it is in the mesh
+    pub colors: Vec<[f32; 4]>,
+    //vertices measured with the ramp last time, every other vertex has the color of the last stop
+    //None when the ramp reaches the whole sphere
+    near: Option<Vec<usize>>,
+    //triangle and cell the colors were measured from
+    source: (usize, usize),
+    //mesh vertices drawing every shared vertex of a flat shaded mesh, one for each triangle using it
+    mesh_vertices: Vec<Vec<usize>>,
+    //version of the layer the colors were drawn from, None when they come from the ramp
+    layer: Option<u64>,
+}
+
+impl ColorCache {
+    //measures every vertex again, for a new mesh, ramp, color mode or layer
+    pub fn rebuild(&mut self, sphere_state: &SphereState, character_state: &CharacterState, color_mode: ColorMode, color_ramp: &ColorRamp, color_layer: &ColorLayer, normal_mode: NormalMode) {
+        let layer_colors = color_layer.0.as_deref().and_then(|name| Some((sphere_state.layers.color_version(name)?, sphere_state.layers.vertex_colors(name, sphere_state)?)));
+        self.layer = layer_colors.as_ref().map(|(version, _)| *version);
+        self.colors = match layer_colors {
+            Some((_, colors)) => colors,
+            None => vertex_colors(sphere_state, character_state, color_mode, color_ramp),
+        };
+        self.near = near_values(sphere_state, character_state, color_mode, color_ramp).map(|near| near.into_iter().map(|(vertex, _)| vertex).collect());
+        self.source = (character_state.current_triangle_id, character_state.current_cell_id);
+        self.mesh_vertices.clear();
+        if normal_mode == NormalMode::Flat {
+            self.mesh_vertices = vec![Vec::new(); sphere_state.vertices.len()];
+            for (mesh_vertex, &vertex) in sphere_state.indices.iter().enumerate() {
+                self.mesh_vertices[vertex as usize].push(mesh_vertex);
+            }
+        }
+    }
+
+    //follows the character onto another triangle or cell, returns the shared vertices whose color changed
+    //the colors only follow the character from triangle to triangle, the great circle distance included
+    pub fn refresh(&mut self, sphere_state: &SphereState, character_state: &CharacterState, color_mode: ColorMode, color_ramp: &ColorRamp) -> Vec<usize> {
+        let source = (character_state.current_triangle_id, character_state.current_cell_id);
+        if source == self.source || color_ramp.metric == ColorMetric::LodLevel || self.layer.is_some() {
+            return Vec::new();
+        }
+        self.source = source;
+
+        let Some(near) = near_values(sphere_state, character_state, color_mode, color_ramp) else {
+            let colors = vertex_colors(sphere_state, character_state, color_mode, color_ramp);
+            let changed = (0..colors.len()).filter(|&vertex| colors[vertex] != self.colors[vertex]).collect();
+            self.colors = colors;
+            return changed;
+        };
+
+        //the vertices left behind fall back to the far color, then the ones near the character are measured
+        let far = color_ramp.sample(f32::INFINITY);
+        let left = self.near.replace(near.iter().map(|&(vertex, _)| vertex).collect()).unwrap_or_default();
+        let mut changed = Vec::new();
+        for (vertex, color) in left.into_iter().map(|vertex| (vertex, far)).chain(near.into_iter().map(|(vertex, value)| (vertex, color_ramp.sample(value)))) {
+            if self.colors[vertex] != color {
+                self.colors[vertex] = color;
+                changed.push(vertex);
+            }
+        }
+        changed.sort_unstable();
+        changed.dedup();
+        changed
+    }
+
+    //copies the colors of the changed shared vertices into the mesh
+    pub fn patch(&self, mesh: &mut Mesh, changed: &[usize], normal_mode: NormalMode) {
+        let Some(VertexAttributeValues::Float32x4(colors)) = mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR) else {
+            return;
+        };
+        for &vertex in changed {
+            match normal_mode {
+                NormalMode::Smooth => colors[vertex] = self.colors[vertex],
+                NormalMode::Flat => {
+                    for &mesh_vertex in &self.mesh_vertices[vertex] {
+                        colors[mesh_vertex] = self.colors[vertex];
+                    }
+                }
+            }
+        }
+    }
+}
+
+//writes the colors of a new mesh, then only patches the vertices near the character when it moves onto another triangle
+//nothing is written while it stays on the same one, a shown layer is only written when it changed
+pub fn update_colors(
+    mut meshes: ResMut<Assets<Mesh>>,
+    sphere_state: Res<SphereState>,
+    character_state: Res<CharacterState>,
+    normal_mode: Res<NormalMode>,
+    colors: ColorSettings,
+    mut cache: Local<ColorCache>,
+) {
+    let ColorSettings { mode: color_mode, ramp: color_ramp, layer: color_layer } = colors;
+    let Some(mesh) = meshes.get(&sphere_state.mesh) else {
+        return;
+    };
+    //meshes are built without colors, see build_sphere_mesh
+    let colored = mesh.attribute(Mesh::ATTRIBUTE_COLOR).is_some_and(|colors| colors.len() == mesh.count_vertices());
+    //a shown layer is drawn again whenever it changed
+    let layer = color_layer.0.as_deref().and_then(|name| sphere_state.layers.color_version(name));
+
+    if !colored || color_mode.is_changed() || color_ramp.is_changed() || color_layer.is_changed() || normal_mode.is_changed() || layer != cache.layer {
+        cache.rebuild(&sphere_state, &character_state, *color_mode, &color_ramp, &color_layer, *normal_mode);
+        if let Some(mesh) = meshes.get_mut(&sphere_state.mesh) {
+            insert_vertex_values(mesh, &sphere_state, Mesh::ATTRIBUTE_COLOR, &cache.colors, *normal_mode);
+        }
+        return;
+    }
+
+    //getting the mesh mutably has it uploaded again, so that only happens when a color changed
+    let changed = cache.refresh(&sphere_state, &character_state, *color_mode, &color_ramp);
+    if !changed.is_empty() {
+        if let Some(mesh) = meshes.get_mut(&sphere_state.mesh) {
+            cache.patch(mesh, &changed, *normal_mode);
+        }
+    }
+}
+
+//color of every shared vertex, the metric of the ramp measured from the character
+pub fn vertex_colors(sphere_state: &SphereState, character_state: &CharacterState, color_mode: ColorMode, color_ramp: &ColorRamp) -> Vec<[f32; 4]> {
+    metric_values(sphere_state, character_state, color_mode, color_ramp.metric)
+        .into_iter()
+        .map(|value| color_ramp.sample(value))
+        .collect()
+}
+
+//value of a metric at every shared vertex, unreachable vertices are infinitely far away
+pub fn metric_values(sphere_state: &SphereState, character_state: &CharacterState, color_mode: ColorMode, metric: ColorMetric) -> Vec<f32> {
+    match (metric, color_mode) {
+        (ColorMetric::RingDistance, ColorMode::Triangles) => triangle_rings(sphere_state, character_state),
+        (ColorMetric::RingDistance, ColorMode::Cells) => cell_rings(sphere_state, character_state),
+        (ColorMetric::GreatCircle, _) => {
+            let center = character_state.center.normalize();
+            sphere_state.vertices.iter().map(|vertex| vertex.normalize().angle_between(center)).collect()
+        }
+        (ColorMetric::LodLevel, _) => {
+            let mut levels = vec![0.0; sphere_state.vertices.len()];
+            for triangle in &sphere_state.triangles {
+                for corner in triangle.corners {
+                    levels[corner as usize] = f32::max(levels[corner as usize], triangle.address.depth() as f32);
+                }
+            }
+            levels
+        }
+    }
+}
+
+//value of the metric at the vertices the ramp can tell apart, every other vertex is past the last stop
+//None when that could be any vertex, for a ramp without an end or a metric not measured from the character
+fn near_values(sphere_state: &SphereState, character_state: &CharacterState, color_mode: ColorMode, color_ramp: &ColorRamp) -> Option<Vec<(usize, f32)>> {
+    let reach = color_ramp.stops.last()?.0;
+    if !reach.is_finite() {
+        return None;
+    }
+    match (color_ramp.metric, color_mode) {
+        (ColorMetric::RingDistance, ColorMode::Triangles) => {
+            let mut distances: HashMap<usize, f32> = HashMap::new();
+            for (triangle, distance) in rings_within(&sphere_state.adjacency.vertex_neighbours, &[character_state.current_triangle_id], reach.floor() as i32) {
+                for corner in sphere_state.triangles[triangle].corners {
+                    let closest = distances.entry(corner as usize).or_insert(f32::INFINITY);
+                    *closest = closest.min(distance as f32);
+                }
+            }
+            Some(distances.into_iter().collect())
+        }
+        (ColorMetric::RingDistance, ColorMode::Cells) => Some(
+            rings_within(&sphere_state.cells.neighbours, &[character_state.current_cell_id], reach.floor() as i32)
+                .into_iter()
+                .map(|(cell, distance)| (cell, distance as f32))
+                .collect(),
+        ),
+        (ColorMetric::GreatCircle, _) => {
+            //flood out from the cell under the character until the cells are past the reach
+            let start = character_state.current_cell_id;
+            if start >= sphere_state.cells.len() {
+                return None;
+            }
+            let center = character_state.center.normalize();
+            let mut found = HashSet::from([start]);
+            let mut queue = VecDeque::from([start]);
+            let mut near = Vec::new();
+            while let Some(cell) = queue.pop_front() {
+                let angle = sphere_state.vertices[cell].normalize().angle_between(center);
+                if angle > reach && cell != start {
+                    continue;
+                }
+                near.push((cell, angle));
+                for &neighbour in &sphere_state.cells.neighbours[cell] {
+                    if found.insert(neighbour) {
+                        queue.push_back(neighbour);
+                    }
+                }
+            }
+            Some(near)
+        }
+        (ColorMetric::LodLevel, _) => None,
+    }
+}
+
+//rings to the triangle under the character, every shared vertex gets the closest of the triangles using it
+fn triangle_rings(sphere_state: &SphereState, character_state: &CharacterState) -> Vec<f32> {
+    let triangle_distances = sphere_state.adjacency.ring_distances(&[character_state.current_triangle_id]);
+    let mut distances: Vec<f32> = vec![f32::INFINITY; sphere_state.vertices.len()];
+
+    for (triangle, &distance) in sphere_state.triangles.iter().zip(&triangle_distances) {
+        //unreachable triangles count as far away
+        let distance = if distance < 0 { f32::INFINITY } else { distance as f32 };
+        for corner in triangle.corners {
+            distances[corner as usize] = distances[corner as usize].min(distance);
+        }
+    }
+    distances
+}
+
+//rings to the cell under the character, the cells are centered on the vertices so each one gets a color of its own
+fn cell_rings(sphere_state: &SphereState, character_state: &CharacterState) -> Vec<f32> {
+    sphere_state
+        .cells
+        .ring_distances(&[character_state.current_cell_id])
+        .into_iter()
+        .map(|distance| if distance < 0 { f32::INFINITY } else { distance as f32 })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{from_ron, load_ron, to_ron};
+
+    #[test]
+    fn ramp_samples_stops_and_loads() {
+        //the default ramp keeps the old red, green and blue rings
+        let ramp = ColorRamp::default();
+        assert_eq!(ramp.sample(0.0), [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(ramp.sample(1.0), [0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(ramp.sample(1.5), [0.0, 1.0, 0.0, 1.0]);
+        assert_eq!(ramp.sample(7.0), [0.0, 0.0, 1.0, 1.0]);
+        assert_eq!(ramp.sample(f32::INFINITY), [0.0, 0.0, 1.0, 1.0]);
+
+        let linear = ColorRamp { interpolation: Interpolation::Linear, ..ramp.clone() };
+        assert_eq!(linear.sample(0.5), [0.5, 0.5, 0.0, 1.0]);
+        assert_eq!(linear.sample(-1.0), [1.0, 0.0, 0.0, 1.0]);
+
+        assert_eq!(from_ron(&to_ron(&ramp)), Ok(ramp.clone()));
+        assert_eq!(load_ron(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/colors.ron")), Ok(ramp));
+        assert!(from_ron::<ColorRamp>("(metric: GreatCircle, interpolation: Linear, stops: [])").is_err());
+        let unsorted: ColorRamp = from_ron("(metric: LodLevel, interpolation: Stepped, stops: [(2.0, (0.0, 0.0, 1.0, 1.0)), (0.0, (1.0, 0.0, 0.0, 1.0))])").unwrap();
+        assert_eq!(unsorted.stops.iter().map(|(value, _)| *value).collect::<Vec<_>>(), vec![0.0, 2.0]);
+    }
+
+    #[test]
+    fn patched_colors_match_a_full_rebuild() {
+        let sphere_state = SphereState::uniform(3);
+        let great_circle = ColorRamp {
+            metric: ColorMetric::GreatCircle,
+            interpolation: Interpolation::Linear,
+            stops: vec![(0.0, [1.0, 0.0, 0.0, 1.0]), (0.5, [0.0, 0.0, 1.0, 1.0])],
+        };
+
+        for (color_mode, color_ramp) in [(ColorMode::Triangles, ColorRamp::default()), (ColorMode::Cells, ColorRamp::default()), (ColorMode::Triangles, great_circle)] {
+            let mut character_state = CharacterState::default();
+            let mut cache = ColorCache::default();
+            let mut source = (usize::MAX, usize::MAX);
+            for i in 0..60 {
+                //walk along a great circle so the character crosses many triangles
+                let center = Quat::from_rotation_x(i as f32 * 0.05).mul_vec3(Vec3::Z);
+                let triangle = sphere_state.locate(center).unwrap();
+                character_state.center = center;
+                character_state.current_triangle_id = triangle;
+                character_state.current_cell_id = sphere_state.locate_cell(center).unwrap();
+
+                if i == 0 {
+                    cache.rebuild(&sphere_state, &character_state, color_mode, &color_ramp, &ColorLayer::default(), NormalMode::Smooth);
+                    continue;
+                }
+                let changed = cache.refresh(&sphere_state, &character_state, color_mode, &color_ramp);
+                assert!(changed.len() < sphere_state.vertices.len() / 4);
+                //the colors are measured again whenever the character steps onto another triangle or cell
+                if std::mem::replace(&mut source, (character_state.current_triangle_id, character_state.current_cell_id)) == source {
+                    continue;
+                }
+                assert_eq!(cache.colors, vertex_colors(&sphere_state, &character_state, color_mode, &color_ramp));
+            }
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::fmt;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadRonError(pub String);
+
+impl fmt::Display for LoadRonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid ron file: {}", self.0)
+    }
+}
+
+impl std::error::Error for LoadRonError {}
+
+//reads a settings file like assets/input.ron or assets/colors.ron
+pub fn load_ron<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T, LoadRonError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|error| LoadRonError(format!("{}: {}", path.display(), error)))?;
+    from_ron(&text).map_err(|error| LoadRonError(format!("{}: {}", path.display(), error.0)))
+}
+
+pub fn from_ron<T: DeserializeOwned>(text: &str) -> Result<T, LoadRonError> {
+    ron::from_str(text).map_err(|error| LoadRonError(error.to_string()))
+}
+
+//writes a settings file, the settings are plain data that always has a ron form
+pub fn to_ron<T: Serialize>(value: &T) -> String {
+    ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()).expect("settings serialize to ron")
+}
@@ -0,0 +1,299 @@
+use std::f32::consts::{FRAC_PI_4, PI};
+
+use bevy_math::Vec3;
+
+use crate::address::TriangleAddress;
+use crate::lod::{NodeShape, SplitTree};
+
+//how the grid of a cube face is laid onto the sphere
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum CubeWarp {
+    //plain projection from the center, the cells at the face corners are over 5 times smaller than at the center
+    #[default]
+    Projected,
+    //moves the grid lines towards the face edges so every cell of a row spans the same angle
+    //this isn't equal area, the cells still differ by a factor of about 1.4
+    EquiAngular,
+    //every cell of a face covers the same area of the sphere, the grid lines are no longer great circles
+    EqualArea,
+}
+
+impl CubeWarp {
+    //point of the sphere for the point u, v of a face, in the frame of the face with the normal along z
+    fn apply(self, u: f32, v: f32) -> Vec3 {
+        match self {
+            CubeWarp::Projected => Vec3::new(u, v, 1.0).normalize(),
+            CubeWarp::EquiAngular => Vec3::new(equi_angular(u), equi_angular(v), 1.0).normalize(),
+            CubeWarp::EqualArea => equal_area(u, v),
+        }
+    }
+}
+
+//point of the cube sphere on the grid of a face at the given depth, i and j run from 0 to 2^depth
+//faces 0 to 5 are +x, -x, +y, -y, +z and -z, u and v are picked so the grid winds counter clockwise seen from outside
+//the grid coordinates are exact in floating point and don't depend on the face they are computed from,
+//points on the edges of the cube are warped in the frame of the first face they lie on,
+//so the corners shared by neighbouring quads, also across the edges of the cube, end up with the same bits
+pub fn cube_point(face: u8, depth: usize, i: u32, j: u32, warp: CubeWarp) -> Vec3 {
+    let step = 2.0 / (1u32 << depth) as f32;
+    let (axis, sign) = ((face / 2) as usize, if face.is_multiple_of(2) { 1.0 } else { -1.0 });
+    let (u_axis, v_axis) = face_axes(axis, sign);
+    let mut cube = [0.0; 3];
+    cube[axis] = sign;
+    cube[u_axis] = i as f32 * step - 1.0;
+    cube[v_axis] = j as f32 * step - 1.0;
+
+    let axis = (0..3).find(|&k| cube[k].abs() == 1.0).unwrap_or(axis);
+    let sign = cube[axis];
+    let (u_axis, v_axis) = face_axes(axis, sign);
+    let local = warp.apply(cube[u_axis], cube[v_axis]);
+    let mut point = [0.0; 3];
+    point[axis] = sign * local.z;
+    point[u_axis] = local.x;
+    point[v_axis] = local.y;
+    Vec3::from_array(point)
+}
+
+//axes of u and v on the face with the normal along the given axis and sign
+fn face_axes(axis: usize, sign: f32) -> (usize, usize) {
+    if sign > 0.0 {
+        ((axis + 1) % 3, (axis + 2) % 3)
+    }
+    else {
+        ((axis + 2) % 3, (axis + 1) % 3)
+    }
+}
+
+//equi-angular mapping of a face coordinate, grid lines stay on great circles
+fn equi_angular(x: f32) -> f32 {
+    //the cube edges stay exact, they are shared with the neighbouring face
+    if x.abs() >= 1.0 {
+        return x;
+    }
+    (x.abs() * FRAC_PI_4).tan().copysign(x)
+}
+
+//equal area mapping, the face is cut along its diagonals into 4 triangles, here the one around the u axis
+//the point goes onto the great circle through the v axis tilted by theta from the normal, at v / u of the way to the diagonal
+//the sphere between two such great circles has the area dtheta times the height along v,
+//so picking theta with the area up to it growing like u^2 and spreading the height evenly keeps every area of the face
+//sin^2 theta = sin(pi u^2 / 6) solves that and reaches the face edge, where theta is pi / 4, at u = 1
+fn equal_area(u: f32, v: f32) -> Vec3 {
+    if u.abs() < v.abs() {
+        let point = equal_area(v, u);
+        return Vec3::new(point.y, point.x, point.z);
+    }
+    if u == 0.0 {
+        return Vec3::Z;
+    }
+    let angle = PI / 12.0 * u * u;
+    let (sin_theta, cos_theta) = ((2.0 * angle).sin().sqrt(), angle.cos() - angle.sin());
+    let height = sin_theta / (1.0 + sin_theta * sin_theta).sqrt() * v / u.abs();
+    let width = (1.0 - height * height).max(0.0).sqrt();
+    Vec3::new(sin_theta.copysign(u) * width, height, cos_theta * width)
+}
+
+//quad of a cube face projected onto the sphere, drawn as a fan of triangles
+#[derive(Clone)]
+pub struct CubeQuad {
+    //face of the cube, 0 to 5
+    pub face: u8,
+    //number of splits between this quad and its face
+    pub depth: usize,
+    //position of the quad on the grid of its face at its depth
+    pub x: u32,
+    pub y: u32,
+    //how the grid of the face is laid onto the sphere
+    pub warp: CubeWarp,
+    //corners counter clockwise seen from outside, child k contains corner k
+    pub corners: [Vec3; 4],
+}
+
+//quadtree over the 6 faces of a cube projected onto the sphere
+pub type QuadTree = SplitTree<CubeQuad>;
+
+impl QuadTree {
+    pub fn new(warp: CubeWarp) -> Self {
+        SplitTree::from_roots((0..6).map(|face| (CubeQuad::new(face, 0, 0, 0, warp), TriangleAddress::root(face))))
+    }
+}
+
+impl CubeQuad {
+    pub fn new(face: u8, depth: usize, x: u32, y: u32, warp: CubeWarp) -> Self {
+        CubeQuad {
+            face,
+            depth,
+            x,
+            y,
+            warp,
+            corners: [(x, y), (x + 1, y), (x + 1, y + 1), (x, y + 1)].map(|(i, j)| cube_point(face, depth, i, j, warp)),
+        }
+    }
+
+    //point of the grid of the face the given number of levels below the quad
+    fn point(&self, levels: usize, i: u32, j: u32) -> Vec3 {
+        cube_point(self.face, self.depth + levels, i, j, self.warp)
+    }
+
+    //corner the diagonal an unsplit quad is cut along starts from, 0 for ac or 1 for bd
+    //the one halving the area more evenly, the cells of a warped face are skewed towards the face corners
+    fn diagonal(&self) -> usize {
+        let [a, b, c, d] = self.corners;
+        let area = |a: Vec3, b: Vec3, c: Vec3| (b - a).cross(c - a).length();
+        let ac = (area(a, b, c) - area(a, c, d)).abs();
+        let bd = (area(b, c, d) - area(b, d, a)).abs();
+        usize::from(bd < ac)
+    }
+
+    //edges ab, bc, cd and da of the quad together with the vertex a split puts on them
+    fn edge_midpoints(&self) -> [(Vec3, Vec3, Vec3); 4] {
+        let (x, y) = (self.x * 2, self.y * 2);
+        let mids = [(x + 1, y), (x + 2, y + 1), (x + 1, y + 2), (x, y + 1)];
+        [0, 1, 2, 3].map(|i| {
+            let (mx, my) = mids[i];
+            (self.corners[i], self.corners[(i + 1) % 4], self.point(1, mx, my))
+        })
+    }
+}
+
+impl NodeShape for CubeQuad {
+    fn corners(&self) -> &[Vec3] {
+        &self.corners
+    }
+
+    fn split(&self) -> [Self; 4] {
+        [(0, 0), (1, 0), (1, 1), (0, 1)].map(|(cx, cy)| CubeQuad::new(self.face, self.depth + 1, self.x * 2 + cx, self.y * 2 + cy, self.warp))
+    }
+
+    //the edge midpoints are corners of two of the children, the center of all four
+    //the center starts on the diagonal an unsplit quad without finer neighbours is drawn with
+    fn new_vertices(&self) -> Vec<(Vec3, Vec3, Vec3, u32)> {
+        let mut vertices: Vec<(Vec3, Vec3, Vec3, u32)> = self.edge_midpoints().into_iter().map(|(a, b, mid)| (a, b, mid, 2)).collect();
+        let diagonal = self.diagonal();
+        vertices.push((self.corners[diagonal], self.corners[diagonal + 2], self.point(1, self.x * 2 + 1, self.y * 2 + 1), 4));
+        vertices
+    }
+
+    fn quarter_points(&self) -> Vec<Vec3> {
+        let (x, y) = (self.x * 4, self.y * 4);
+        [(x + 1, y), (x + 3, y), (x + 4, y + 1), (x + 4, y + 3), (x + 3, y + 4), (x + 1, y + 4), (x, y + 3), (x, y + 1)]
+            .into_iter()
+            .map(|(i, j)| self.point(2, i, j))
+            .collect()
+    }
+
+    //the corners together with the midpoints a finer neighbour put on the edges, fanned so the triangles are already watertight
+    //the outline starts at the diagonal, so a plain quad is cut along it
+    fn triangles(&self, has_vertex: impl Fn(Vec3) -> bool) -> Vec<[Vec3; 3]> {
+        let mut outline = Vec::with_capacity(8);
+        let edges = self.edge_midpoints();
+        for i in (0..4).map(|k| (k + self.diagonal()) % 4) {
+            let (_, _, mid) = edges[i];
+            outline.push((self.corners[i], true));
+            if has_vertex(mid) {
+                outline.push((mid, false));
+            }
+        }
+        fan(&outline)
+    }
+}
+
+//triangles covering a quad outline, fanned from a corner without midpoints next to it where there is one
+//otherwise from a midpoint, a corner would make a flat triangle with the halves of a split edge next to it
+//each entry of the outline is a vertex and whether it is a corner, a plain quad abcd becomes the triangles abc and acd
+fn fan(outline: &[(Vec3, bool)]) -> Vec<[Vec3; 3]> {
+    let n = outline.len();
+    let apex = (0..n)
+        .find(|&i| outline[i].1 && outline[(i + 1) % n].1 && outline[(i + n - 1) % n].1)
+        .or_else(|| (0..n).find(|&i| !outline[i].1))
+        .unwrap_or(0);
+    (1..n - 1)
+        .map(|k| [outline[apex].0, outline[(apex + k) % n].0, outline[(apex + k + 1) % n].0])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use bevy_math::Quat;
+
+    use super::*;
+    use crate::lod::tests::{assert_watertight, settings};
+    use crate::morph::Geomorph;
+
+    fn assert_quads_watertight(tree: &mut QuadTree) {
+        let (_, triangles) = tree.leaves();
+        assert_watertight(&triangles.iter().map(|triangle| triangle.corners).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn uniform_cube_sphere_shares_every_vertex() {
+        for warp in [CubeWarp::Projected, CubeWarp::EquiAngular, CubeWarp::EqualArea] {
+            let mut tree = QuadTree::new(warp);
+            while tree.update(&[], 3, &settings(), &mut Geomorph::instant()) {}
+            let (vertices, triangles) = tree.leaves();
+            assert_eq!(triangles.len(), 12 * 64);
+            assert_eq!(vertices.len(), 6 * 64 + 2);
+            assert_quads_watertight(&mut tree);
+
+            for triangle in &triangles {
+                let point = triangle.triangle.centroid().normalize();
+                assert_eq!(tree.locate(point), Some(triangle.index));
+            }
+        }
+    }
+
+    #[test]
+    fn quad_lod_stays_watertight_while_focus_moves() {
+        let mut tree = QuadTree::new(CubeWarp::EqualArea);
+        let mut focus = Vec3::new(0.3, 0.2, 1.0).normalize();
+        for _ in 0..40 {
+            focus = Quat::from_rotation_y(0.1).mul_vec3(focus);
+            while tree.update(&[focus], 0, &settings(), &mut Geomorph::instant()) {}
+            assert_quads_watertight(&mut tree);
+
+            //fanned quads give each of their triangles its own piece
+            let (_, triangles) = tree.leaves();
+            let addresses: HashSet<_> = triangles.iter().map(|triangle| &triangle.address).collect();
+            assert_eq!(addresses.len(), triangles.len());
+            for triangle in &triangles {
+                let [a, b, c] = triangle.triangle.vertices;
+                assert!(a.dot(b.cross(c)) > 1e-6, "flat triangle {}", triangle.address);
+                assert_eq!(tree.locate(triangle.triangle.centroid().normalize()), Some(triangle.index));
+            }
+        }
+    }
+    #[test]
+    fn equal_area_cube_sphere_keeps_the_triangle_areas_even() {
+        let area_ratio = |warp| {
+            let mut tree = QuadTree::new(warp);
+            while tree.update(&[], 4, &settings(), &mut Geomorph::instant()) {}
+            let (_, triangles) = tree.leaves();
+            let areas: Vec<f32> = triangles
+                .iter()
+                .map(|triangle| {
+                    let [a, b, c] = triangle.triangle.vertices;
+                    (b - a).cross(c - a).length()
+                })
+                .collect();
+            assert_eq!(areas.len(), 3072);
+            areas.iter().copied().fold(0.0, f32::max) / areas.iter().copied().fold(f32::MAX, f32::min)
+        };
+        assert!(area_ratio(CubeWarp::Projected) > 4.0);
+        assert!(area_ratio(CubeWarp::EquiAngular) > 1.3);
+        assert!(area_ratio(CubeWarp::EqualArea) < 1.06, "{}", area_ratio(CubeWarp::EqualArea));
+    }
+
+    #[test]
+    fn equal_area_matches_the_face_edges_and_corners() {
+        //the edges of a face lie on the great circles through the cube edges, the corners on the cube diagonals
+        for v in [-1.0, -0.5, 0.0, 0.25, 1.0] {
+            let point = equal_area(1.0, v);
+            assert!((point.x - point.z).abs() < 1e-6);
+            assert!((point.length() - 1.0).abs() < 1e-6);
+        }
+        assert!(equal_area(1.0, 1.0).distance(Vec3::ONE.normalize()) < 1e-6);
+        assert!(equal_area(-1.0, 1.0).distance(Vec3::new(-1.0, 1.0, 1.0).normalize()) < 1e-6);
+    }
+}
@@ -0,0 +1,599 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use bevy_math::primitives::Triangle3d;
+use bevy_math::Vec3;
+
+use crate::address::TriangleAddress;
+
+//geometry of the geodesic sphere, plain math without any bevy app, meshes or assets
+//so tools and tests can generate the same planet headless, building without the app feature leaves only this core
+
+//golden ratio, (1 + √5) / 2 rounded to f32
+pub const PHI: f32 = 1.618_034;
+
+#[derive(Clone)]
+pub struct Triangle {
+    //position of the triangle in SphereState::triangles
+    pub index: usize,
+    pub triangle: Triangle3d,
+    //positions of the corners in the shared vertex buffer
+    pub corners: [u32; 3],
+    //where the triangle sits in the subdivision hierarchy, stable across subdivision changes
+    //frequency_subdivide numbers the triangles of each face instead, see TriangleAddress::frequency
+    pub address: TriangleAddress,
+}
+
+//the vertex subdivide creates on the edge between a and b
+pub fn edge_midpoint(a: Vec3, b: Vec3) -> Vec3 {
+    a.midpoint(b).normalize()
+}
+
+//corners of the four triangles a triangle is split into, given its corners and the midpoints of its edges ab, bc and ca
+//shared by subdivide and the lod tree so both produce the same children in the same order
+pub fn child_corners<T: Copy>([a, b, c]: [T; 3], [ab, bc, ca]: [T; 3]) -> [[T; 3]; 4] {
+    [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+}
+
+//polyhedra a geodesic sphere can be grown from, all with triangular faces so they go through the same subdivide
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Polyhedron {
+    //20 faces, the most even triangles
+    #[default]
+    Icosahedron,
+    //8 faces, one per quadrant of latitude and longitude
+    Octahedron,
+    //4 faces, the most uneven triangles
+    Tetrahedron,
+}
+
+impl Polyhedron {
+    //base vertices and faces
+    pub fn faces(self) -> (Vec<Vec3>, Vec<Triangle>) {
+        match self {
+            Polyhedron::Icosahedron => icosahedron(),
+            Polyhedron::Octahedron => octahedron(),
+            Polyhedron::Tetrahedron => tetrahedron(),
+        }
+    }
+}
+
+//base vertices and faces of the geodesic sphere
+pub fn icosahedron() -> (Vec<Vec3>, Vec<Triangle>) {
+
+    //define unit sphere vertices for icosahedron
+    let vertices: Vec<Vec3> = vec![
+        Vec3::new(-1.0,  PHI, 0.0).normalize(),
+        Vec3::new( 1.0,  PHI, 0.0).normalize(),
+        Vec3::new(-1.0, -PHI, 0.0).normalize(),
+        Vec3::new( 1.0, -PHI, 0.0).normalize(),
+
+        Vec3::new(0.0, -1.0,  PHI).normalize(),
+        Vec3::new(0.0,  1.0,  PHI).normalize(),
+        Vec3::new(0.0, -1.0, -PHI).normalize(),
+        Vec3::new(0.0,  1.0, -PHI).normalize(),
+
+        Vec3::new( PHI, 0.0, -1.0).normalize(),
+        Vec3::new( PHI, 0.0,  1.0).normalize(),
+        Vec3::new(-PHI, 0.0, -1.0).normalize(),
+        Vec3::new(-PHI, 0.0,  1.0).normalize(),
+    ];
+
+    let faces: [[u32; 3]; 20] = [
+        [0, 11, 5], [0, 5, 1], [0, 1, 7], [0, 7, 10], [0, 10, 11],
+
+        [1, 5, 9], [5, 11, 4], [11, 10, 2], [10, 7, 6], [7, 1, 8],
+
+        [3, 9, 4], [3, 4, 2], [3, 2, 6], [3, 6, 8], [3, 8, 9],
+
+        [4, 9, 5], [2, 4, 11], [6, 2, 10], [8, 6, 7], [9, 8, 1],
+    ];
+
+    let triangles = base_triangles(&vertices, &faces);
+    (vertices, triangles)
+}
+
+//octahedron with its corners on the axes
+//faces 0 to 3 are the northern hemisphere (+y) and 4 to 7 the southern one, each in order of longitude starting at -z
+//so face = 4 * hemisphere + quadrant, with the longitude measured as atan2(x, z) like HeightImage does
+pub fn octahedron() -> (Vec<Vec3>, Vec<Triangle>) {
+    let vertices: Vec<Vec3> = vec![Vec3::X, Vec3::NEG_X, Vec3::Y, Vec3::NEG_Y, Vec3::Z, Vec3::NEG_Z];
+
+    let faces: [[u32; 3]; 8] = [
+        [1, 2, 5], [1, 4, 2], [0, 2, 4], [0, 5, 2],
+
+        [1, 5, 3], [1, 3, 4], [0, 4, 3], [0, 3, 5],
+    ];
+
+    let triangles = base_triangles(&vertices, &faces);
+    (vertices, triangles)
+}
+
+//tetrahedron with its corners on alternate corners of a cube, face i is opposite corner 3 - i
+pub fn tetrahedron() -> (Vec<Vec3>, Vec<Triangle>) {
+    let vertices: Vec<Vec3> = vec![
+        Vec3::new( 1.0,  1.0,  1.0).normalize(),
+        Vec3::new( 1.0, -1.0, -1.0).normalize(),
+        Vec3::new(-1.0,  1.0, -1.0).normalize(),
+        Vec3::new(-1.0, -1.0,  1.0).normalize(),
+    ];
+
+    let faces: [[u32; 3]; 4] = [[0, 1, 2], [0, 3, 1], [0, 2, 3], [1, 3, 2]];
+
+    let triangles = base_triangles(&vertices, &faces);
+    (vertices, triangles)
+}
+
+//root triangles of a polyhedron, faces are wound counter clockwise seen from outside
+fn base_triangles(vertices: &[Vec3], faces: &[[u32; 3]]) -> Vec<Triangle> {
+    faces.iter().enumerate().map(|(index, &corners)| Triangle {
+        index,
+        triangle: Triangle3d::new(vertices[corners[0] as usize], vertices[corners[1] as usize], vertices[corners[2] as usize]),
+        corners,
+        address: TriangleAddress::root(index as u8),
+    }).collect()
+}
+
+//icosahedron subdivided the given number of times
+pub fn geodesic_triangles(subdivisions: usize) -> (Vec<Vec3>, Vec<Triangle>) {
+    let (mut vertices, mut triangles) = icosahedron();
+    for _ in 0..subdivisions {
+        (vertices, triangles) = subdivide(vertices, triangles);
+    }
+    (vertices, triangles)
+}
+
+//vertex and index buffers of the geodesic sphere, three indices per triangle wound counter clockwise seen from outside
+pub fn geodesic_sphere(subdivisions: usize) -> (Vec<Vec3>, Vec<u32>) {
+    let (vertices, triangles) = geodesic_triangles(subdivisions);
+    (vertices, index_buffer(&triangles))
+}
+
+//flattens the corners of the triangles into an index buffer
+pub fn index_buffer(triangles: &[Triangle]) -> Vec<u32> {
+    triangles.iter().flat_map(|triangle| triangle.corners).collect()
+}
+
+//normal of every vertex, the area weighted average of the triangles around it
+//works on the final positions, so displaced terrain gets its own normals
+pub fn smooth_normals(positions: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+    for triangle in indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+        //the length of the cross product is twice the area of the triangle
+        let normal = (b - a).cross(c - a);
+        for &corner in triangle {
+            normals[corner as usize] += normal;
+        }
+    }
+    normals
+        .into_iter()
+        .zip(positions)
+        .map(|(normal, position)| normal.try_normalize().unwrap_or(position.normalize_or_zero()))
+        .collect()
+}
+
+//normal of every triangle corner in the order of the indices, all three corners of a triangle share the normal of its plane
+pub fn flat_normals(positions: &[Vec3], indices: &[u32]) -> Vec<Vec3> {
+    indices
+        .chunks_exact(3)
+        .flat_map(|triangle| {
+            let [a, b, c] = [0, 1, 2].map(|i| positions[triangle[i] as usize]);
+            [(b - a).cross(c - a).normalize_or_zero(); 3]
+        })
+        .collect()
+}
+
+//vertices and triangles of every uniform subdivision level up to and including the given one
+pub fn uniform_levels(base: Polyhedron, subdivisions: usize) -> Vec<(Vec<Vec3>, Vec<Triangle>)> {
+    let mut levels = vec![base.faces()];
+    for _ in 0..subdivisions {
+        let (vertices, triangles) = levels[levels.len() - 1].clone();
+        levels.push(subdivide(vertices, triangles));
+    }
+    levels
+}
+
+pub fn subdivide(mut vertices: Vec<Vec3>, triangles: Vec<Triangle>) -> (Vec<Vec3>, Vec<Triangle>) {
+    let mut new_triangles: Vec<Triangle> = Vec::with_capacity(triangles.len() * 4);
+    //midpoint of every edge, so the triangles on both sides of an edge share it
+    let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+        for triangle in triangles {
+
+            //get corners of triangle
+            let [a, b, c] = triangle.corners;
+
+            //get new vertices, created once per edge
+            let ab = midpoint_index(&mut vertices, &mut midpoints, a, b);
+            let bc = midpoint_index(&mut vertices, &mut midpoints, b, c);
+            let ca = midpoint_index(&mut vertices, &mut midpoints, c, a);
+
+            for (child, corners) in child_corners([a, b, c], [ab, bc, ca]).into_iter().enumerate() {
+                new_triangles.push(Triangle {
+                    index: new_triangles.len(),
+                    triangle: Triangle3d::new(vertices[corners[0] as usize], vertices[corners[1] as usize], vertices[corners[2] as usize]),
+                    corners,
+                    address: triangle.address.child(child as u8),
+                });
+            }
+        }
+
+    (vertices, new_triangles)
+}
+
+//base faces split into 4 depth times, with the vertices placed the given way
+pub fn uniform_subdivide(mut vertices: Vec<Vec3>, mut triangles: Vec<Triangle>, depth: usize, placement: Placement) -> (Vec<Vec3>, Vec<Triangle>) {
+    let base_vertices = vertices.len();
+    for _ in 0..depth {
+        (vertices, triangles) = subdivide(vertices, triangles);
+    }
+    if let Placement::Relaxed { iterations } = placement {
+        relax(&mut vertices, &mut triangles, base_vertices, iterations);
+    }
+    (vertices, triangles)
+}
+
+//index of the midpoint between two vertices, adding it to the vertex buffer the first time the edge is seen
+pub fn midpoint_index(vertices: &mut Vec<Vec3>, midpoints: &mut HashMap<(u32, u32), u32>, a: u32, b: u32) -> u32 {
+    *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+        vertices.push(edge_midpoint(vertices[a as usize], vertices[b as usize]));
+        (vertices.len() - 1) as u32
+    })
+}
+
+//where the vertices of a uniform sphere are put, by frequency_subdivide and uniform_subdivide
+//midpoint subdivision always halves the arc between two unit vectors, which normalized and slerp placement agree on
+//relaxing moves the midpoints off their parents' edges, so relaxed midpoint spheres can't be geomorphed between levels
+//the lod and cube trees keep their vertices on the edge midpoints whatever the placement
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Placement {
+    //flat grid points pushed onto the sphere, triangles near the base corners come out smaller than the ones in the middle
+    #[default]
+    Normalized,
+    //grid points spaced evenly along great circles, first along the edges then along each row
+    Slerp,
+    //slerp placement, then the vertices are moved for the given number of passes so the triangle areas even out
+    Relaxed { iterations: usize },
+}
+
+//class I geodesic subdivision, every base edge is split into frequency segments and every base face into frequency² triangles
+//unlike subdivide the triangle count doesn't have to grow by a power of 4, frequency 3 gives 9 triangles per face
+//the grid point i, j of the face a, b, c is a + (b - a) * i / frequency + (c - a) * j / frequency pushed onto the sphere
+//each face emits its triangles row by row along i, the up triangle (i, j), (i + 1, j), (i, j + 1) followed by the down triangle next to it
+//see frequency_index for where a triangle ends up in the list
+//the small triangles aren't halves of halves, their addresses number them within their base face, see TriangleAddress::frequency
+//looking a triangle up by the corners of its address doesn't work on these spheres, use the index or TriangleLocator::Frequency instead
+pub fn frequency_subdivide(mut vertices: Vec<Vec3>, triangles: Vec<Triangle>, frequency: usize, placement: Placement) -> (Vec<Vec3>, Vec<Triangle>) {
+    let n = frequency.max(1);
+    let base_vertices = vertices.len();
+    let mut new_triangles: Vec<Triangle> = Vec::with_capacity(triangles.len() * n * n);
+    //points along every base edge, keyed by the edge and the step from its lower vertex, so both faces of an edge share them
+    let mut edge_points: HashMap<(u32, u32, usize), u32> = HashMap::new();
+
+    for triangle in triangles {
+        let [a, b, c] = triangle.corners;
+        let [pa, pb, pc] = [a, b, c].map(|corner| vertices[corner as usize]);
+        let face_start = new_triangles.len();
+
+        //vertex index of every grid point, row i has n + 1 - i points
+        let mut grid: Vec<Vec<u32>> = Vec::with_capacity(n + 1);
+        for i in 0..=n {
+            let mut row = Vec::with_capacity(n + 1 - i);
+            for j in 0..=n - i {
+                let index = if i == 0 && j == 0 {
+                    a
+                } else if i == n {
+                    b
+                } else if j == n {
+                    c
+                } else if j == 0 {
+                    edge_point_index(&mut vertices, &mut edge_points, a, b, i, n, placement)
+                } else if i == 0 {
+                    edge_point_index(&mut vertices, &mut edge_points, a, c, j, n, placement)
+                } else if i + j == n {
+                    edge_point_index(&mut vertices, &mut edge_points, b, c, j, n, placement)
+                } else {
+                    let point = match placement {
+                        Placement::Normalized => (pa + (pb - pa) * (i as f32 / n as f32) + (pc - pa) * (j as f32 / n as f32)).normalize(),
+                        //along the row from the edge ab to the edge bc
+                        Placement::Slerp | Placement::Relaxed { .. } => {
+                            let start = great_circle_point(pa, pb, i as f32 / n as f32);
+                            let end = great_circle_point(pb, pc, (n - i) as f32 / n as f32);
+                            great_circle_point(start, end, j as f32 / (n - i) as f32)
+                        }
+                    };
+                    vertices.push(point);
+                    (vertices.len() - 1) as u32
+                };
+                row.push(index);
+            }
+            grid.push(row);
+        }
+
+        for i in 0..n {
+            for j in 0..n - i {
+                let mut cells = vec![[grid[i][j], grid[i + 1][j], grid[i][j + 1]]];
+                //the last cell of a row only has the up triangle
+                if i + j + 1 < n {
+                    cells.push([grid[i + 1][j], grid[i + 1][j + 1], grid[i][j + 1]]);
+                }
+                for corners in cells {
+                    new_triangles.push(Triangle {
+                        index: new_triangles.len(),
+                        triangle: Triangle3d::new(vertices[corners[0] as usize], vertices[corners[1] as usize], vertices[corners[2] as usize]),
+                        corners,
+                        address: TriangleAddress::frequency(triangle.address.face, new_triangles.len() - face_start, n),
+                    });
+                }
+            }
+        }
+    }
+
+    if let Placement::Relaxed { iterations } = placement {
+        relax(&mut vertices, &mut new_triangles, base_vertices, iterations);
+    }
+
+    (vertices, new_triangles)
+}
+
+//point a fraction t of the way along the great circle arc between two unit vectors
+pub fn great_circle_point(a: Vec3, b: Vec3, t: f32) -> Vec3 {
+    let angle = a.angle_between(b);
+    if angle < 1e-6 {
+        return a.lerp(b, t).normalize();
+    }
+    ((a * ((1.0 - t) * angle).sin() + b * (t * angle).sin()) / angle.sin()).normalize()
+}
+
+//moves the vertices over the sphere so the triangles grow or shrink towards the mean area
+//every pass takes a step down the gradient of the squared area errors, the first fixed vertices stay where they are
+//the corner positions of the triangles are updated as well
+pub fn relax(vertices: &mut [Vec3], triangles: &mut [Triangle], fixed: usize, iterations: usize) {
+    //fraction of the gradient moved per pass, larger steps start to fold triangles over near the base corners
+    let rate = 0.2;
+    for _ in 0..iterations {
+        let areas: Vec<f32> = triangles.iter().map(|triangle| triangle_area(vertices, triangle.corners)).collect();
+        let mean = areas.iter().sum::<f32>() / areas.len().max(1) as f32;
+
+        let mut gradients = vec![Vec3::ZERO; vertices.len()];
+        for (triangle, area) in triangles.iter().zip(&areas) {
+            let [a, b, c] = triangle.corners.map(|corner| vertices[corner as usize]);
+            let normal = (b - a).cross(c - a).normalize_or_zero();
+            let error = area / mean - 1.0;
+            //moving a corner away from the opposite edge grows the triangle, by half the length of that edge
+            for (corner, (q, r)) in triangle.corners.into_iter().zip([(b, c), (c, a), (a, b)]) {
+                gradients[corner as usize] += normal.cross(r - q) * (0.5 * error);
+            }
+        }
+
+        for (vertex, gradient) in vertices.iter_mut().zip(gradients).skip(fixed) {
+            *vertex = (*vertex - gradient * rate).normalize();
+        }
+    }
+
+    for triangle in triangles.iter_mut() {
+        let [a, b, c] = triangle.corners.map(|corner| vertices[corner as usize]);
+        triangle.triangle = Triangle3d::new(a, b, c);
+    }
+}
+
+fn triangle_area(vertices: &[Vec3], corners: [u32; 3]) -> f32 {
+    let [a, b, c] = corners.map(|corner| vertices[corner as usize]);
+    (b - a).cross(c - a).length() * 0.5
+}
+
+//spread of the triangle areas and edge lengths of a mesh, flat triangles between the vertices
+#[derive(Clone, Copy, Debug)]
+pub struct MeshStats {
+    pub triangles: usize,
+    pub min_area: f32,
+    pub max_area: f32,
+    pub mean_area: f32,
+    pub min_edge: f32,
+    pub max_edge: f32,
+    pub mean_edge: f32,
+}
+
+impl MeshStats {
+    pub fn new(vertices: &[Vec3], triangles: &[Triangle]) -> Self {
+        let mut stats = MeshStats {
+            triangles: triangles.len(),
+            min_area: f32::MAX,
+            max_area: 0.0,
+            mean_area: 0.0,
+            min_edge: f32::MAX,
+            max_edge: 0.0,
+            mean_edge: 0.0,
+        };
+        for triangle in triangles {
+            let area = triangle_area(vertices, triangle.corners);
+            stats.min_area = stats.min_area.min(area);
+            stats.max_area = stats.max_area.max(area);
+            stats.mean_area += area;
+
+            let [a, b, c] = triangle.corners.map(|corner| vertices[corner as usize]);
+            //every edge of a closed mesh is counted once from each side, which doesn't change the mean
+            for edge in [a.distance(b), b.distance(c), c.distance(a)] {
+                stats.min_edge = stats.min_edge.min(edge);
+                stats.max_edge = stats.max_edge.max(edge);
+                stats.mean_edge += edge;
+            }
+        }
+        let count = triangles.len().max(1) as f32;
+        stats.mean_area /= count;
+        stats.mean_edge /= count * 3.0;
+        stats
+    }
+
+    //largest triangle over the smallest one, 1 for a perfectly even mesh
+    pub fn area_ratio(&self) -> f32 {
+        self.max_area / self.min_area
+    }
+
+    //longest edge over the shortest one
+    pub fn edge_ratio(&self) -> f32 {
+        self.max_edge / self.min_edge
+    }
+}
+
+impl fmt::Display for MeshStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} triangles, area {:.3e} to {:.3e} (mean {:.3e}, ratio {:.3}), edge {:.3e} to {:.3e} (mean {:.3e}, ratio {:.3})",
+            self.triangles, self.min_area, self.max_area, self.mean_area, self.area_ratio(),
+            self.min_edge, self.max_edge, self.mean_edge, self.edge_ratio(),
+        )
+    }
+}
+
+//stats of the given polyhedron split at the given frequency with every placement strategy, one line each
+pub fn placement_report(base: Polyhedron, frequency: usize, placements: &[Placement]) -> String {
+    report(placements, |placement| {
+        let (vertices, triangles) = base.faces();
+        let (vertices, triangles) = frequency_subdivide(vertices, triangles, frequency, placement);
+        format!("{:?} frequency {} {:?}: {}\n", base, frequency, placement, MeshStats::new(&vertices, &triangles))
+    })
+}
+
+//stats of the given polyhedron subdivided depth times with every placement strategy, one line each
+pub fn midpoint_placement_report(base: Polyhedron, depth: usize, placements: &[Placement]) -> String {
+    report(placements, |placement| {
+        let (vertices, triangles) = base.faces();
+        let (vertices, triangles) = uniform_subdivide(vertices, triangles, depth, placement);
+        format!("{:?} depth {} {:?}: {}\n", base, depth, placement, MeshStats::new(&vertices, &triangles))
+    })
+}
+
+fn report(placements: &[Placement], line: impl Fn(Placement) -> String) -> String {
+    placements.iter().map(|&placement| line(placement)).collect()
+}
+
+//position of the triangle at grid cell i, j of a face in the list made by frequency_subdivide
+//rows before i hold 2 * (n - i) - 1 triangles each, which sums to i * (2n - i)
+pub fn frequency_index(face: usize, i: usize, j: usize, down: bool, frequency: usize) -> usize {
+    face * frequency * frequency + i * (2 * frequency - i) + 2 * j + down as usize
+}
+
+//index of the point step / n of the way along an edge, adding it to the vertex buffer the first time the edge is seen
+//the point is always measured from the lower vertex, so both faces of the edge compute the same position
+fn edge_point_index(vertices: &mut Vec<Vec3>, edge_points: &mut HashMap<(u32, u32, usize), u32>, a: u32, b: u32, step: usize, n: usize, placement: Placement) -> u32 {
+    let (low, high, step) = if a < b { (a, b, step) } else { (b, a, n - step) };
+    *edge_points.entry((low, high, step)).or_insert_with(|| {
+        let (p, q, t) = (vertices[low as usize], vertices[high as usize], step as f32 / n as f32);
+        let point = match placement {
+            Placement::Normalized => p.lerp(q, t).normalize(),
+            Placement::Slerp | Placement::Relaxed { .. } => great_circle_point(p, q, t),
+        };
+        vertices.push(point);
+        (vertices.len() - 1) as u32
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn geodesic_sphere_shares_every_vertex() {
+        for subdivisions in 0..4 {
+            let (vertices, indices) = geodesic_sphere(subdivisions);
+            let triangles = 20 * 4usize.pow(subdivisions as u32);
+            assert_eq!(indices.len(), triangles * 3);
+            //euler characteristic of a closed triangle mesh with every vertex shared: v = t / 2 + 2
+            assert_eq!(vertices.len(), triangles / 2 + 2);
+            assert!(vertices.iter().all(|vertex| (vertex.length() - 1.0).abs() < 1e-5));
+            assert!(indices.iter().all(|&index| (index as usize) < vertices.len()));
+        }
+    }
+
+    #[test]
+    fn smooth_normals_of_the_unit_sphere_point_outwards() {
+        let (vertices, indices) = geodesic_sphere(3);
+        for (normal, vertex) in smooth_normals(&vertices, &indices).into_iter().zip(&vertices) {
+            assert!(normal.dot(*vertex) > 0.99);
+        }
+    }
+
+    #[test]
+    fn triangles_wind_outwards() {
+        for base in [Polyhedron::Icosahedron, Polyhedron::Octahedron, Polyhedron::Tetrahedron] {
+            let (vertices, triangles) = uniform_levels(base, 2).pop().unwrap();
+            //every face splits into 16 and every vertex is shared, so v = t / 2 + 2 like for the icosahedron
+            assert_eq!(triangles.len(), base.faces().1.len() * 16);
+            assert_eq!(vertices.len(), triangles.len() / 2 + 2);
+            for triangle in triangles {
+                let [a, b, c] = triangle.triangle.vertices;
+                assert!((b - a).cross(c - a).dot(a + b + c) > 0.0, "{:?} face {} is wound inwards", base, triangle.address);
+            }
+        }
+    }
+
+    #[test]
+    fn frequency_subdivision_shares_every_vertex() {
+        for frequency in [1, 3, 5, 7] {
+            let (vertices, triangles) = icosahedron();
+            let (vertices, triangles) = frequency_subdivide(vertices, triangles, frequency, Placement::Normalized);
+            assert_eq!(triangles.len(), 20 * frequency * frequency);
+            assert_eq!(vertices.len(), triangles.len() / 2 + 2);
+            assert!(vertices.iter().all(|vertex| (vertex.length() - 1.0).abs() < 1e-5));
+            for triangle in triangles {
+                let [a, b, c] = triangle.triangle.vertices;
+                assert!((b - a).cross(c - a).dot(a + b + c) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn frequency_subdivision_gives_every_triangle_its_own_address() {
+        for (frequency, depth) in [(1, 0), (3, 2), (4, 2), (6, 3)] {
+            let (vertices, triangles) = icosahedron();
+            let (_, triangles) = frequency_subdivide(vertices, triangles, frequency, Placement::Slerp);
+            let addresses: HashSet<_> = triangles.iter().map(|triangle| &triangle.address).collect();
+            assert_eq!(addresses.len(), triangles.len());
+            for triangle in &triangles {
+                assert_eq!(triangle.address.depth(), depth);
+                if frequency.is_power_of_two() {
+                    assert_eq!(triangle.address.index(), triangle.index);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn relaxed_placement_evens_out_the_areas() {
+        let stats = [Placement::Normalized, Placement::Slerp, Placement::Relaxed { iterations: 50 }].map(|placement| {
+            let (vertices, triangles) = icosahedron();
+            let (vertices, triangles) = frequency_subdivide(vertices, triangles, 8, placement);
+            for triangle in &triangles {
+                let [a, b, c] = triangle.triangle.vertices;
+                assert!((b - a).cross(c - a).dot(a + b + c) > 0.0);
+            }
+            MeshStats::new(&vertices, &triangles)
+        });
+        assert!(stats[2].area_ratio() < stats[0].area_ratio());
+        assert!(stats[2].area_ratio() < stats[1].area_ratio());
+    }
+
+    #[test]
+    fn relaxed_placement_evens_out_midpoint_spheres() {
+        let [normalized, slerp, relaxed] = [Placement::Normalized, Placement::Slerp, Placement::Relaxed { iterations: 50 }].map(|placement| {
+            let (vertices, triangles) = icosahedron();
+            let (vertices, triangles) = uniform_subdivide(vertices, triangles, 3, placement);
+            assert_eq!(triangles.len(), 20 * 64);
+            for triangle in &triangles {
+                let [a, b, c] = triangle.triangle.vertices;
+                assert_eq!(triangle.triangle.vertices, triangle.corners.map(|corner| vertices[corner as usize]));
+                assert!((b - a).cross(c - a).dot(a + b + c) > 0.0);
+            }
+            let stats = MeshStats::new(&vertices, &triangles);
+            (vertices, stats)
+        });
+        //halving an arc is the same either way
+        assert_eq!(normalized.0, slerp.0);
+        assert!(relaxed.1.area_ratio() < normalized.1.area_ratio());
+        assert_eq!(midpoint_placement_report(Polyhedron::Octahedron, 2, &[Placement::Normalized, Placement::Slerp]).lines().count(), 2);
+    }
+}
@@ -0,0 +1,240 @@
+use std::f32::consts::{PI, TAU};
+use std::sync::Arc;
+
+use bevy_math::Vec3;
+
+//terrain height of the sphere surface, added to the unit radius along the vertex normal
+//heights are sampled by direction from the center, so every subdivision level and lod leaf sees the same terrain
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "app", derive(bevy::prelude::Resource))]
+pub enum Heightmap {
+    //perfect unit sphere
+    #[default]
+    Flat,
+    //smooth rolling hills
+    Fractal(FractalNoise),
+    //sharp mountain ridges
+    Ridged(FractalNoise),
+    //height sampled from an equirectangular image
+    Image(HeightImage),
+    //any other height function of the direction
+    Custom(Arc<dyn Fn(Vec3) -> f32 + Send + Sync>),
+}
+
+//octaves of gradient noise summed together, each one finer and weaker than the last
+#[derive(Clone)]
+pub struct FractalNoise {
+    //different seeds give different terrain
+    pub seed: u32,
+    pub octaves: usize,
+    //frequency of the first octave, in features per unit of distance on the sphere
+    pub frequency: f32,
+    //frequency multiplier between octaves
+    pub lacunarity: f32,
+    //amplitude multiplier between octaves
+    pub persistence: f32,
+    //largest height the noise reaches, as a fraction of the radius
+    pub amplitude: f32,
+}
+
+impl Default for FractalNoise {
+    fn default() -> Self {
+        FractalNoise {
+            seed: 0,
+            octaves: 5,
+            frequency: 2.0,
+            lacunarity: 2.0,
+            persistence: 0.5,
+            amplitude: 0.05,
+        }
+    }
+}
+
+//grid of heights wrapped around the sphere, x runs along the longitude and y from the north pole (+y) to the south pole
+#[derive(Clone)]
+pub struct HeightImage {
+    pub width: usize,
+    pub height: usize,
+    //row major, 0 to 1
+    pub values: Vec<f32>,
+    //height of a value of 1, as a fraction of the radius
+    pub amplitude: f32,
+}
+
+impl HeightImage {
+    //reads the first channel of an 8 bit image
+    #[cfg(feature = "app")]
+    pub fn from_image(image: &bevy::prelude::Image, amplitude: f32) -> Self {
+        let width = image.width() as usize;
+        let height = image.height() as usize;
+        let stride = (image.data.len() / (width * height).max(1)).max(1);
+        HeightImage {
+            width,
+            height,
+            values: image.data.iter().step_by(stride).map(|&value| value as f32 / 255.0).collect(),
+            amplitude,
+        }
+    }
+
+    //bilinear sample at the given direction
+    pub fn sample(&self, direction: Vec3) -> f32 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        let u = (0.5 + direction.x.atan2(direction.z) / TAU) * self.width as f32 - 0.5;
+        let v = (direction.y.clamp(-1.0, 1.0).acos() / PI) * self.height as f32 - 0.5;
+        let (x, y) = (u.floor(), v.floor());
+        let (fx, fy) = (u - x, v - y);
+
+        //wrap around the longitude, clamp at the poles
+        let value = |x: f32, y: f32| {
+            let x = (x as i64).rem_euclid(self.width as i64) as usize;
+            let y = (y as i64).clamp(0, self.height as i64 - 1) as usize;
+            self.values[y * self.width + x]
+        };
+        let top = value(x, y) + (value(x + 1.0, y) - value(x, y)) * fx;
+        let bottom = value(x, y + 1.0) + (value(x + 1.0, y + 1.0) - value(x, y + 1.0)) * fx;
+        top + (bottom - top) * fy
+    }
+}
+
+impl Heightmap {
+    //height above the unit sphere at a direction from the center
+    pub fn height(&self, direction: Vec3) -> f32 {
+        match self {
+            Heightmap::Flat => 0.0,
+            Heightmap::Fractal(noise) => noise.amplitude * noise.fractal(direction),
+            Heightmap::Ridged(noise) => noise.amplitude * noise.ridged(direction),
+            Heightmap::Image(image) => image.amplitude * image.sample(direction),
+            Heightmap::Custom(height) => height(direction),
+        }
+    }
+
+    //moves a vertex of the unit sphere onto the terrain
+    pub fn displace(&self, vertex: Vec3) -> Vec3 {
+        if let Heightmap::Flat = self {
+            return vertex;
+        }
+        let direction = vertex.normalize();
+        direction * (1.0 + self.height(direction))
+    }
+
+    //normal of the terrain at a direction from the center, found from two nearby points on the surface
+    pub fn normal(&self, direction: Vec3) -> Vec3 {
+        let direction = direction.normalize();
+        if let Heightmap::Flat = self {
+            return direction;
+        }
+        let tangent = direction.any_orthonormal_vector();
+        let bitangent = direction.cross(tangent);
+        let step = 1e-3;
+        let center = self.displace(direction);
+        let along_tangent = self.displace(direction + tangent * step) - center;
+        let along_bitangent = self.displace(direction + bitangent * step) - center;
+        along_tangent.cross(along_bitangent).try_normalize().unwrap_or(direction)
+    }
+}
+
+impl FractalNoise {
+    //sum of the octaves, roughly -1 to 1
+    pub fn fractal(&self, direction: Vec3) -> f32 {
+        self.octaves(direction, |noise| noise)
+    }
+
+    //sum of folded octaves, 0 in the valleys and 1 on the ridges
+    pub fn ridged(&self, direction: Vec3) -> f32 {
+        self.octaves(direction, |noise| {
+            let ridge = 1.0 - noise.abs();
+            ridge * ridge
+        })
+    }
+
+    fn octaves(&self, direction: Vec3, shape: impl Fn(f32) -> f32) -> f32 {
+        let mut frequency = self.frequency;
+        let mut amplitude = 1.0;
+        let mut total = 0.0;
+        let mut weight = 0.0;
+        for octave in 0..self.octaves {
+            let seed = self.seed.wrapping_add(octave as u32);
+            total += amplitude * shape(gradient_noise(direction * frequency, seed));
+            weight += amplitude;
+            frequency *= self.lacunarity;
+            amplitude *= self.persistence;
+        }
+        if weight > 0.0 {
+            total / weight
+        } else {
+            0.0
+        }
+    }
+}
+
+//3d gradient noise, 0 on every lattice point and roughly -1 to 1 in between
+pub fn gradient_noise(point: Vec3, seed: u32) -> f32 {
+    let cell = point.floor();
+    let local = point - cell;
+    //quintic fade, so the terrain normals are continuous across cells
+    let fade = local * local * local * (local * (local * 6.0 - 15.0) + 10.0);
+    let [x, y, z] = cell.to_array().map(|value| value as i32);
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let offset = Vec3::new(dx as f32, dy as f32, dz as f32);
+        gradient(hash(x + dx, y + dy, z + dz, seed)).dot(local - offset)
+    };
+    let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+
+    let x00 = lerp(corner(0, 0, 0), corner(1, 0, 0), fade.x);
+    let x10 = lerp(corner(0, 1, 0), corner(1, 1, 0), fade.x);
+    let x01 = lerp(corner(0, 0, 1), corner(1, 0, 1), fade.x);
+    let x11 = lerp(corner(0, 1, 1), corner(1, 1, 1), fade.x);
+    lerp(lerp(x00, x10, fade.y), lerp(x01, x11, fade.y), fade.z)
+}
+
+fn hash(x: i32, y: i32, z: i32, seed: u32) -> u32 {
+    let mut hash = seed
+        ^ (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1_e995);
+    hash ^ (hash >> 15)
+}
+
+//one of the 12 edge directions of a cube, as in improved perlin noise
+fn gradient(hash: u32) -> Vec3 {
+    match hash % 12 {
+        0 => Vec3::new(1.0, 1.0, 0.0),
+        1 => Vec3::new(-1.0, 1.0, 0.0),
+        2 => Vec3::new(1.0, -1.0, 0.0),
+        3 => Vec3::new(-1.0, -1.0, 0.0),
+        4 => Vec3::new(1.0, 0.0, 1.0),
+        5 => Vec3::new(-1.0, 0.0, 1.0),
+        6 => Vec3::new(1.0, 0.0, -1.0),
+        7 => Vec3::new(-1.0, 0.0, -1.0),
+        8 => Vec3::new(0.0, 1.0, 1.0),
+        9 => Vec3::new(0.0, -1.0, 1.0),
+        10 => Vec3::new(0.0, 1.0, -1.0),
+        _ => Vec3::new(0.0, -1.0, -1.0),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    //unit vectors spread over the sphere, from the north pole down to the south pole
+    pub(crate) fn directions(count: usize) -> impl Iterator<Item = Vec3> {
+        (0..count).map(move |i| Vec3::new((i as f32 * 0.37).sin(), (i as f32 * 0.11).cos(), 1.0 - 2.0 * i as f32 / count as f32).normalize())
+    }
+
+    #[test]
+    fn displaced_surface_follows_the_height() {
+        let heightmap = Heightmap::Ridged(FractalNoise::default());
+        for direction in directions(100) {
+            let height = heightmap.height(direction);
+            assert!((0.0..=0.05).contains(&height));
+            assert!((heightmap.displace(direction).length() - 1.0 - height).abs() < 1e-5);
+            assert!(heightmap.normal(direction).dot(direction) > 0.0);
+        }
+    }
+}
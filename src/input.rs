@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+//what the player can do with the character
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+    Forward,
+    Back,
+    TurnLeft,
+    TurnRight,
+    StrafeLeft,
+    StrafeRight,
+    Jump,
+}
+
+//what a gamepad axis drives, positive stick values move forward, turn right and strafe right
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum AxisAction {
+    Move,
+    Turn,
+    Strafe,
+}
+
+//bindings of the actions to keys and gamepad inputs, several inputs can trigger the same action
+//the defaults can be replaced by a ron file read with load_ron, see assets/input.ron
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InputMap {
+    pub keys: Vec<(KeyCode, Action)>,
+    pub gamepad_buttons: Vec<(GamepadButtonType, Action)>,
+    pub gamepad_axes: Vec<(GamepadAxisType, AxisAction)>,
+    //stick values closer to the center than this are ignored
+    pub dead_zone: f32,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        InputMap {
+            keys: vec![
+                (KeyCode::KeyW, Action::Forward),
+                (KeyCode::ArrowUp, Action::Forward),
+                (KeyCode::KeyS, Action::Back),
+                (KeyCode::ArrowDown, Action::Back),
+                (KeyCode::KeyA, Action::TurnLeft),
+                (KeyCode::ArrowLeft, Action::TurnLeft),
+                (KeyCode::KeyD, Action::TurnRight),
+                (KeyCode::ArrowRight, Action::TurnRight),
+                (KeyCode::KeyQ, Action::StrafeLeft),
+                (KeyCode::KeyE, Action::StrafeRight),
+                (KeyCode::Space, Action::Jump),
+            ],
+            gamepad_buttons: vec![
+                (GamepadButtonType::South, Action::Jump),
+            ],
+            gamepad_axes: vec![
+                (GamepadAxisType::LeftStickY, AxisAction::Move),
+                (GamepadAxisType::LeftStickX, AxisAction::Strafe),
+                (GamepadAxisType::RightStickX, AxisAction::Turn),
+            ],
+            dead_zone: 0.15,
+        }
+    }
+}
+
+impl InputMap {
+    //state of the actions this frame, from every bound key, button and stick
+    pub fn character_input(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        gamepads: &Gamepads,
+        gamepad_buttons: &ButtonInput<GamepadButton>,
+        gamepad_axes: &Axis<GamepadAxis>,
+    ) -> CharacterInput {
+        let mut input = CharacterInput::default();
+        let mut apply = |action: Action, pressed: bool, just_pressed: bool| {
+            if !pressed {
+                return;
+            }
+            match action {
+                Action::Forward => input.forward += 1.0,
+                Action::Back => input.forward -= 1.0,
+                Action::TurnLeft => input.turn -= 1.0,
+                Action::TurnRight => input.turn += 1.0,
+                Action::StrafeLeft => input.strafe -= 1.0,
+                Action::StrafeRight => input.strafe += 1.0,
+                Action::Jump => input.jump |= just_pressed,
+            }
+        };
+
+        for &(key, action) in &self.keys {
+            apply(action, keys.pressed(key), keys.just_pressed(key));
+        }
+        for gamepad in gamepads.iter() {
+            for &(button, action) in &self.gamepad_buttons {
+                let button = GamepadButton::new(gamepad, button);
+                apply(action, gamepad_buttons.pressed(button), gamepad_buttons.just_pressed(button));
+            }
+        }
+
+        for gamepad in gamepads.iter() {
+            for &(axis, action) in &self.gamepad_axes {
+                let value = gamepad_axes.get(GamepadAxis::new(gamepad, axis)).unwrap_or(0.0);
+                if value.abs() < self.dead_zone {
+                    continue;
+                }
+                match action {
+                    AxisAction::Move => input.forward += value,
+                    AxisAction::Turn => input.turn += value,
+                    AxisAction::Strafe => input.strafe += value,
+                }
+            }
+        }
+
+        input.forward = input.forward.clamp(-1.0, 1.0);
+        input.turn = input.turn.clamp(-1.0, 1.0);
+        input.strafe = input.strafe.clamp(-1.0, 1.0);
+        input
+    }
+}
+
+//what the player wants the character to do this frame, read by handle_character_movement
+#[derive(Resource, Clone, Copy, Default, PartialEq, Debug)]
+pub struct CharacterInput {
+    //-1 to 1, positive walks forward
+    pub forward: f32,
+    //-1 to 1, positive turns right
+    pub turn: f32,
+    //-1 to 1, positive steps to the right
+    pub strafe: f32,
+    //true on the frame the jump was pressed
+    pub jump: bool,
+}
+
+//reads the held keys and sticks every frame, so holding a key moves smoothly instead of relying on key repeat
+pub fn read_input(
+    input_map: Res<InputMap>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    mut character_input: ResMut<CharacterInput>,
+) {
+    *character_input = input_map.character_input(&keys, &gamepads, &gamepad_buttons, &gamepad_axes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{from_ron, load_ron, to_ron};
+
+    #[test]
+    fn held_keys_and_config_round_trip() {
+        let input_map = InputMap::default();
+        assert_eq!(from_ron(&to_ron(&input_map)), Ok(input_map.clone()));
+        assert_eq!(load_ron(concat!(env!("CARGO_MANIFEST_DIR"), "/assets/input.ron")), Ok(input_map.clone()));
+
+        let mut keys = ButtonInput::default();
+        keys.press(KeyCode::KeyW);
+        keys.press(KeyCode::ArrowUp);
+        keys.press(KeyCode::KeyD);
+        keys.press(KeyCode::Space);
+        let input = input_map.character_input(&keys, &Gamepads::default(), &ButtonInput::default(), &Axis::default());
+        assert_eq!(input, CharacterInput { forward: 1.0, turn: 1.0, strafe: 0.0, jump: true });
+
+        //still held on the next frame, but the jump only triggers once
+        keys.clear();
+        let input = input_map.character_input(&keys, &Gamepads::default(), &ButtonInput::default(), &Axis::default());
+        assert_eq!(input, CharacterInput { forward: 1.0, turn: 1.0, strafe: 0.0, jump: false });
+    }
+}
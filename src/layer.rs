@@ -0,0 +1,326 @@
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::geometry::Triangle;
+use crate::locate::containment;
+use crate::sphere::SphereState;
+
+//most triangles a finer triangle looks through for the coarser one covering it
+const INHERIT_SEARCH: usize = 512;
+
+//data that can be stored per triangle
+pub trait LayerValue: Clone + Default + Send + Sync + 'static {
+    //value of a triangle covering several old ones, each given with the area it covers
+    fn average(values: &[(Self, f32)]) -> Self;
+}
+
+impl LayerValue for f32 {
+    fn average(values: &[(Self, f32)]) -> Self {
+        let area: f32 = values.iter().map(|(_, area)| area).sum();
+        if area <= 0.0 {
+            return values.first().map(|(value, _)| *value).unwrap_or_default();
+        }
+        values.iter().map(|(value, area)| value * area).sum::<f32>() / area
+    }
+}
+
+impl LayerValue for Vec3 {
+    fn average(values: &[(Self, f32)]) -> Self {
+        let area: f32 = values.iter().map(|(_, area)| area).sum();
+        if area <= 0.0 {
+            return values.first().map(|(value, _)| *value).unwrap_or_default();
+        }
+        values.iter().map(|(value, area)| *value * *area).sum::<Vec3>() / area
+    }
+}
+
+//ids can't be blended, the one covering most of the area wins
+macro_rules! majority_layer_value {
+    ($($value:ty),*) => {
+        $(
+            impl LayerValue for $value {
+                fn average(values: &[(Self, f32)]) -> Self {
+                    let mut areas: Vec<(Self, f32)> = Vec::new();
+                    for (value, area) in values {
+                        match areas.iter_mut().find(|(other, _)| other == value) {
+                            Some((_, total)) => *total += area,
+                            None => areas.push((value.clone(), *area)),
+                        }
+                    }
+                    areas.into_iter().max_by(|a, b| a.1.total_cmp(&b.1)).map(|(value, _)| value).unwrap_or_default()
+                }
+            }
+        )*
+    };
+}
+
+majority_layer_value!(bool, u8, u16, u32, u64, usize, i32);
+
+//values stored per triangle of the sphere, in the order of SphereState::triangles
+#[derive(Clone)]
+pub struct TriangleLayer<T> {
+    pub values: Vec<T>,
+    //turns a value into a color when the layer is shown instead of the ramp, see ColorLayer
+    pub color: Option<fn(&T) -> [f32; 4]>,
+}
+
+impl<T: LayerValue> TriangleLayer<T> {
+    pub fn new(triangle_count: usize) -> Self {
+        TriangleLayer {
+            values: vec![T::default(); triangle_count],
+            color: None,
+        }
+    }
+
+    pub fn with_colors(mut self, color: fn(&T) -> [f32; 4]) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn resample(&self, resampling: &Resampling) -> Self {
+        let values = resampling
+            .sources
+            .iter()
+            .map(|sources| match sources.as_slice() {
+                [] => T::default(),
+                //the same triangle or a finer one inside it keeps the value as it is
+                [(old, _)] => self.values.get(*old).cloned().unwrap_or_default(),
+                sources => T::average(&sources.iter().filter_map(|&(old, area)| Some((self.values.get(old)?.clone(), area))).collect::<Vec<_>>()),
+            })
+            .collect();
+        TriangleLayer { values, color: self.color }
+    }
+}
+
+//where the value of every new triangle comes from when the triangles of the sphere are replaced
+pub struct Resampling {
+    //old triangles and the area they cover, one list per new triangle
+    pub sources: Vec<Vec<(usize, f32)>>,
+}
+
+impl Resampling {
+    //the centers of old triangles as fine as the new ones or finer land in the new triangle covering them, which averages them
+    //new triangles finer than the old ones have no center landing in them, they inherit from the old triangle covering their own center
+    //only the new triangles are located, the trees of the sphere are already updated when this runs
+    pub fn new(old: &[Triangle], sphere_state: &SphereState) -> Self {
+        let mut sources: Vec<Vec<(usize, f32)>> = vec![Vec::new(); sphere_state.triangles.len()];
+        for (index, triangle) in old.iter().enumerate() {
+            if let Some(new) = sphere_state.locate(triangle.triangle.centroid().normalize()) {
+                sources[new].push((index, triangle.triangle.area()));
+            }
+        }
+
+        //the old triangle covering a finer one landed in a new triangle close by, so the search spreads out from it
+        let inherited: Vec<Option<usize>> = (0..sources.len())
+            .map(|new| {
+                if !sources[new].is_empty() {
+                    return None;
+                }
+                let center = sphere_state.triangles[new].triangle.centroid().normalize();
+                let mut found = HashSet::from([new]);
+                let mut queue = VecDeque::from([new]);
+                let mut best: Option<(usize, f32)> = None;
+                while let Some(current) = queue.pop_front() {
+                    for &(index, _) in &sources[current] {
+                        let inside = containment(&old[index].triangle, center);
+                        if best.is_none_or(|(_, best)| inside > best) {
+                            best = Some((index, inside));
+                        }
+                    }
+                    if best.is_some_and(|(_, inside)| inside >= 0.0) || found.len() > INHERIT_SEARCH {
+                        break;
+                    }
+                    for &neighbour in &sphere_state.adjacency.vertex_neighbours[current] {
+                        if found.insert(neighbour) {
+                            queue.push_back(neighbour);
+                        }
+                    }
+                }
+                best.map(|(index, _)| index)
+            })
+            .collect();
+
+        for (new, old) in inherited.into_iter().enumerate() {
+            if let Some(old) = old {
+                sources[new].push((old, 1.0));
+            }
+        }
+        Resampling { sources }
+    }
+}
+
+//a TriangleLayer of any value type
+pub trait AnyLayer: Send + Sync {
+    fn resample(&self, resampling: &Resampling) -> Box<dyn AnyLayer>;
+    fn has_colors(&self) -> bool;
+    //color of every triangle, None without a color function
+    fn triangle_colors(&self) -> Option<Vec<[f32; 4]>>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn clone_layer(&self) -> Box<dyn AnyLayer>;
+}
+
+impl<T: LayerValue> AnyLayer for TriangleLayer<T> {
+    fn resample(&self, resampling: &Resampling) -> Box<dyn AnyLayer> {
+        Box::new(TriangleLayer::resample(self, resampling))
+    }
+
+    fn has_colors(&self) -> bool {
+        self.color.is_some()
+    }
+
+    fn triangle_colors(&self) -> Option<Vec<[f32; 4]>> {
+        let color = self.color?;
+        Some(self.values.iter().map(color).collect())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn clone_layer(&self) -> Box<dyn AnyLayer> {
+        Box::new(self.clone())
+    }
+}
+
+//named layers of a sphere, resampled together whenever its triangles are replaced
+#[derive(Default)]
+pub struct TriangleLayers {
+    //layer and when it last changed, so colors drawn from it know when to update
+    layers: HashMap<String, (Box<dyn AnyLayer>, u64)>,
+    //counts every change of every layer, a changed layer gets the count as its version
+    changes: u64,
+}
+
+impl Clone for TriangleLayers {
+    fn clone(&self) -> Self {
+        TriangleLayers {
+            layers: self.layers.iter().map(|(name, (layer, version))| (name.clone(), (layer.clone_layer(), *version))).collect(),
+            changes: self.changes,
+        }
+    }
+}
+
+impl TriangleLayers {
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    //replaces the layer with the same name, the layer needs a value for every triangle
+    pub fn insert<T: LayerValue>(&mut self, name: impl Into<String>, layer: TriangleLayer<T>) {
+        self.changes += 1;
+        self.layers.insert(name.into(), (Box::new(layer), self.changes));
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.layers.remove(name);
+    }
+
+    //None if there is no layer with the name or it holds other values
+    pub fn get<T: LayerValue>(&self, name: &str) -> Option<&TriangleLayer<T>> {
+        self.layers.get(name)?.0.as_any().downcast_ref()
+    }
+
+    //counts as a change of the layer, whether or not the values are written to
+    pub fn get_mut<T: LayerValue>(&mut self, name: &str) -> Option<&mut TriangleLayer<T>> {
+        let (layer, version) = self.layers.get_mut(name)?;
+        self.changes += 1;
+        *version = self.changes;
+        layer.as_any_mut().downcast_mut()
+    }
+
+    //changes whenever the layer is inserted, resampled or borrowed mutably
+    pub fn version(&self, name: &str) -> Option<u64> {
+        self.layers.get(name).map(|(_, version)| *version)
+    }
+
+    //version of a layer that can be drawn, one with a color function
+    pub fn color_version(&self, name: &str) -> Option<u64> {
+        self.layers.get(name).filter(|(layer, _)| layer.has_colors()).map(|(_, version)| *version)
+    }
+
+    pub fn resample(&mut self, resampling: &Resampling) {
+        self.changes += 1;
+        for (layer, version) in self.layers.values_mut() {
+            *layer = layer.resample(resampling);
+            *version = self.changes;
+        }
+    }
+
+    //color of every triangle, None if there is no layer with the name or it has no color function
+    pub fn triangle_colors(&self, name: &str) -> Option<Vec<[f32; 4]>> {
+        self.layers.get(name)?.0.triangle_colors()
+    }
+
+    //colors of the shared vertices, each the average of the colors of the triangles using it
+    //None if there is no layer with the name or it has no color function
+    pub fn vertex_colors(&self, name: &str, sphere_state: &SphereState) -> Option<Vec<[f32; 4]>> {
+        let triangle_colors = self.triangle_colors(name)?;
+        let mut sums = vec![(Vec4::ZERO, 0.0); sphere_state.vertices.len()];
+        for (triangle, color) in sphere_state.triangles.iter().zip(triangle_colors) {
+            for corner in triangle.corners {
+                let (sum, count) = &mut sums[corner as usize];
+                *sum += Vec4::from(color);
+                *count += 1.0;
+            }
+        }
+        Some(sums.into_iter().map(|(sum, count)| if count > 0.0 { (sum / count).into() } else { [1.0; 4] }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //resubdivides the sphere in place, so its layers are resampled
+    fn uniform(sphere_state: &mut SphereState, depth: usize) {
+        let SphereState { vertices, triangles, locator, .. } = SphereState::uniform(depth);
+        sphere_state.set_triangles(vertices, triangles, locator);
+    }
+
+    #[test]
+    fn layers_follow_the_triangles_across_subdivisions() {
+        let mut sphere_state = SphereState::default();
+        uniform(&mut sphere_state, 2);
+        let elevation = TriangleLayer { values: sphere_state.triangles.iter().map(|triangle| triangle.index as f32).collect(), color: None };
+        let biome = TriangleLayer { values: sphere_state.triangles.iter().map(|triangle| triangle.index as u32 % 3).collect(), color: None };
+        sphere_state.layers.insert("elevation", elevation.clone());
+        sphere_state.layers.insert("biome", biome.clone());
+
+        //refining hands every child the value of its parent
+        uniform(&mut sphere_state, 3);
+        let refined = sphere_state.layers.get::<f32>("elevation").unwrap();
+        for triangle in &sphere_state.triangles {
+            let parent = triangle.address.parent().unwrap().index();
+            assert_eq!(refined.values[triangle.index], elevation.values[parent]);
+        }
+
+        //coarsening averages the children, which all still hold the value of the parent
+        uniform(&mut sphere_state, 2);
+        let coarsened = sphere_state.layers.get::<f32>("elevation").unwrap();
+        for (value, original) in coarsened.values.iter().zip(&elevation.values) {
+            assert!((value - original).abs() < 1e-3);
+        }
+        assert_eq!(sphere_state.layers.get::<u32>("biome").unwrap().values, biome.values);
+        assert!(sphere_state.layers.get::<u32>("elevation").is_none());
+
+        //coarsening blends different children by the area they cover
+        uniform(&mut sphere_state, 3);
+        let children = TriangleLayer {
+            values: sphere_state.triangles.iter().map(|triangle| if triangle.address.path.last() == Some(&3) { 4.0 } else { 0.0 }).collect(),
+            color: None,
+        };
+        sphere_state.layers.insert("children", children.with_colors(|&value| [value, 0.0, 0.0, 1.0]));
+        uniform(&mut sphere_state, 2);
+        for &value in &sphere_state.layers.get::<f32>("children").unwrap().values {
+            assert!(value > 0.5 && value < 1.5);
+        }
+        assert_eq!(sphere_state.layers.vertex_colors("children", &sphere_state).map(|colors| colors.len()), Some(sphere_state.vertices.len()));
+    }
+}
@@ -0,0 +1,89 @@
+//geometry core, plain math on bevy_math types that builds and tests without the engine
+pub mod address;
+pub mod adjacency;
+pub mod cells;
+pub mod config;
+pub mod cube;
+pub mod geometry;
+pub mod height;
+pub mod locate;
+pub mod lod;
+pub mod morph;
+
+//the plugin drawing the sphere and the character walking on it, behind the app feature
+#[cfg(feature = "app")]
+pub mod camera;
+#[cfg(feature = "app")]
+pub mod character;
+#[cfg(feature = "app")]
+pub mod colors;
+#[cfg(feature = "app")]
+pub mod input;
+#[cfg(feature = "app")]
+pub mod layer;
+#[cfg(feature = "app")]
+pub mod material;
+#[cfg(feature = "app")]
+pub mod sphere;
+
+#[cfg(feature = "app")]
+use bevy::prelude::*;
+
+pub use config::{load_ron, LoadRonError};
+pub use geometry::{Placement, Triangle};
+pub use height::Heightmap;
+pub use lod::LodSettings;
+pub use morph::Geomorph;
+
+#[cfg(feature = "app")]
+pub use camera::{CameraMode, CameraSettings};
+#[cfg(feature = "app")]
+pub use character::{Character, CharacterState, MoveSpeed};
+#[cfg(feature = "app")]
+pub use colors::{ColorLayer, ColorMetric, ColorMode, ColorRamp};
+#[cfg(feature = "app")]
+pub use input::{CharacterInput, InputMap};
+#[cfg(feature = "app")]
+pub use layer::{LayerValue, TriangleLayer};
+#[cfg(feature = "app")]
+pub use material::{TriangleDataPlugin, TriangleOverlay};
+#[cfg(feature = "app")]
+pub use sphere::{BaseShape, NormalMode, SphereState, SubdivisionMode, Subdivisions};
+
+//marks the camera placed by CameraSettings, its position is also used as a focus point by the lod tree next to the character
+#[cfg(feature = "app")]
+#[derive(Component)]
+pub struct LodCamera;
+
+//geodesic sphere with distance based lod and a character walking on it
+//resources already in the app are kept, so they can be inserted beforehand to change the defaults
+//the app provides the camera, lights and WireframePlugin, the plugin moves the camera marked with LodCamera
+#[cfg(feature = "app")]
+pub struct QuadtreeLodPlugin;
+
+#[cfg(feature = "app")]
+impl Plugin for QuadtreeLodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Subdivisions>()
+            .init_resource::<BaseShape>()
+            .init_resource::<SphereState>()
+            .init_resource::<CharacterState>()
+            .init_resource::<LodSettings>()
+            .init_resource::<Geomorph>()
+            .init_resource::<Heightmap>()
+            .init_resource::<NormalMode>()
+            .init_resource::<InputMap>()
+            .init_resource::<CharacterInput>()
+            .init_resource::<CameraSettings>()
+            .init_resource::<ColorMode>()
+            .init_resource::<ColorRamp>()
+            .init_resource::<ColorLayer>()
+            .add_systems(Startup, (sphere::spawn_sphere, character::spawn_character))
+            .add_systems(Update, (input::read_input, character::handle_character_movement, camera::update_camera).chain())
+            .add_systems(Update, sphere::apply_sphere_settings)
+            .add_systems(Update, sphere::update_lod)
+            .add_systems(Update, sphere::apply_geomorph)
+            //the systems above replace the mesh without colors
+            .add_systems(Update, colors::update_colors.after(character::handle_character_movement).after(sphere::apply_sphere_settings).after(sphere::update_lod).after(sphere::apply_geomorph));
+    }
+}
@@ -0,0 +1,169 @@
+use bevy_math::primitives::Triangle3d;
+use bevy_math::Vec3;
+
+use crate::address::TriangleAddress;
+use crate::adjacency::TriangleAdjacency;
+use crate::geometry::{frequency_index, Triangle};
+
+//finds the triangle under a point on the unit sphere by descending from the base faces, one level at a time
+#[derive(Clone, Default)]
+pub enum TriangleLocator {
+    //no triangles yet
+    #[default]
+    Empty,
+    //base faces subdivided depth times, child k of triangle i is triangle 4 * i + k
+    //the guess is corrected with walk, which only moves when the vertices were relaxed
+    Uniform { base: Vec<Triangle3d>, depth: usize },
+    //base faces split into frequency² triangles each by frequency_subdivide
+    Frequency { base: Vec<Triangle3d>, frequency: usize },
+    //triangles are the leaves of the lod tree, which does the lookup itself
+    Lod,
+    //triangles are the fanned leaves of the cube sphere quadtree, which does the lookup itself
+    Cube,
+}
+
+impl TriangleLocator {
+    pub fn uniform(base: &[Triangle], depth: usize) -> Self {
+        TriangleLocator::Uniform {
+            base: base.iter().map(|triangle| triangle.triangle).collect(),
+            depth,
+        }
+    }
+
+    pub fn frequency(base: &[Triangle], frequency: usize) -> Self {
+        TriangleLocator::Frequency {
+            base: base.iter().map(|triangle| triangle.triangle).collect(),
+            frequency,
+        }
+    }
+}
+
+//how far inside the spherical triangle a point is, positive inside and negative outside
+//the triangle with the highest value is the one containing the point, also along shared edges
+pub fn containment(triangle: &Triangle3d, point: Vec3) -> f32 {
+    polygon_containment(&triangle.vertices, point)
+}
+
+//containment of a convex spherical polygon with its corners counter clockwise seen from outside, like the quads of the cube sphere
+pub fn polygon_containment(corners: &[Vec3], point: Vec3) -> f32 {
+    (0..corners.len())
+        .map(|i| point.dot(corners[i].cross(corners[(i + 1) % corners.len()])))
+        .fold(f32::INFINITY, f32::min)
+}
+
+//index of the triangle that contains the point
+pub fn best_fit<'a>(triangles: impl IntoIterator<Item = &'a Triangle3d>, point: Vec3) -> Option<usize> {
+    triangles
+        .into_iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| containment(a, point).total_cmp(&containment(b, point)))
+        .map(|(index, _)| index)
+}
+
+//index of the uniformly subdivided triangle containing the point
+pub fn locate_uniform(base: &[Triangle3d], depth: usize, point: Vec3) -> Option<usize> {
+    TriangleAddress::from_point(base, point, depth).map(|address| address.index())
+}
+
+//index of the triangle made by frequency_subdivide containing the point
+//the grid points are flat grid points pushed outwards from the center, so projecting the point back onto the plane of
+//its base face lands on the flat grid, where the cell is found directly from the barycentric coordinates
+pub fn locate_frequency(base: &[Triangle3d], frequency: usize, point: Vec3) -> Option<usize> {
+    let face = best_fit(base, point)?;
+    let [a, b, c] = base[face].vertices;
+    let (ab, ac) = (b - a, c - a);
+    let normal = ab.cross(ac);
+    let along = point.dot(normal);
+    if along <= 0.0 {
+        return None;
+    }
+    let projected = point * (a.dot(normal) / along) - a;
+
+    //barycentric coordinates along ab and ac, scaled to grid steps
+    let (d00, d01, d11) = (ab.dot(ab), ab.dot(ac), ac.dot(ac));
+    let (d20, d21) = (projected.dot(ab), projected.dot(ac));
+    let denominator = d00 * d11 - d01 * d01;
+    let n = frequency.max(1);
+    let u = ((d11 * d20 - d01 * d21) / denominator * n as f32).max(0.0);
+    let v = ((d00 * d21 - d01 * d20) / denominator * n as f32).max(0.0);
+
+    let i = (u.floor() as usize).min(n - 1);
+    let j = (v.floor() as usize).min(n - 1 - i);
+    let down = i + j + 1 < n && (u - i as f32) + (v - j as f32) > 1.0;
+    Some(frequency_index(face, i, j, down, n))
+}
+
+//moves from a triangle to the neighbour across the edge the point lies furthest behind, until the point is inside
+//used to correct a guess when the vertices aren't exactly where the locator expects them, like after relaxing
+pub fn walk(triangles: &[Triangle], adjacency: &TriangleAdjacency, start: usize, point: Vec3) -> usize {
+    let mut current = start;
+    //every step goes to a triangle containing the point better, the limit only guards against ties
+    for _ in 0..triangles.len() {
+        let best = adjacency.edge_neighbours[current]
+            .iter()
+            .flatten()
+            .copied()
+            .max_by(|&a, &b| containment(&triangles[a].triangle, point).total_cmp(&containment(&triangles[b].triangle, point)));
+        match best {
+            Some(next) if containment(&triangles[next].triangle, point) > containment(&triangles[current].triangle, point) => current = next,
+            _ => break,
+        }
+    }
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{frequency_subdivide, octahedron, uniform_levels, uniform_subdivide, Placement, Polyhedron};
+
+    #[test]
+    fn uniform_locator_finds_every_triangle() {
+        let base: Vec<Triangle3d> = Polyhedron::Icosahedron.faces().1.iter().map(|triangle| triangle.triangle).collect();
+        for (depth, (_, triangles)) in uniform_levels(Polyhedron::Icosahedron, 4).into_iter().enumerate() {
+            for triangle in triangles {
+                let center = triangle.triangle.centroid().normalize();
+                assert_eq!(locate_uniform(&base, depth, center), Some(triangle.index));
+            }
+        }
+    }
+
+    #[test]
+    fn frequency_locator_finds_every_triangle() {
+        for frequency in [1, 2, 3, 5] {
+            let (vertices, base) = octahedron();
+            let locator = TriangleLocator::frequency(&base, frequency);
+            let (_, triangles) = frequency_subdivide(vertices, base, frequency, Placement::Normalized);
+            let TriangleLocator::Frequency { base, frequency } = locator else { unreachable!() };
+            for triangle in triangles {
+                let center = triangle.triangle.centroid().normalize();
+                assert_eq!(locate_frequency(&base, frequency, center), Some(triangle.index));
+            }
+        }
+    }
+
+    #[test]
+    fn walking_corrects_moved_vertices() {
+        for placement in [Placement::Slerp, Placement::Relaxed { iterations: 20 }] {
+            let (vertices, base) = octahedron();
+            let base_triangles: Vec<Triangle3d> = base.iter().map(|triangle| triangle.triangle).collect();
+            let (vertices, triangles) = frequency_subdivide(vertices, base, 6, placement);
+            let adjacency = TriangleAdjacency::new(vertices.len(), &triangles);
+            for triangle in &triangles {
+                let center = triangle.triangle.centroid().normalize();
+                let guess = locate_frequency(&base_triangles, 6, center).unwrap();
+                assert_eq!(walk(&triangles, &adjacency, guess, center), triangle.index);
+            }
+        }
+
+        let (vertices, base) = octahedron();
+        let base_triangles: Vec<Triangle3d> = base.iter().map(|triangle| triangle.triangle).collect();
+        let (vertices, triangles) = uniform_subdivide(vertices, base, 3, Placement::Relaxed { iterations: 20 });
+        let adjacency = TriangleAdjacency::new(vertices.len(), &triangles);
+        for triangle in &triangles {
+            let center = triangle.triangle.centroid().normalize();
+            let guess = locate_uniform(&base_triangles, 3, center).unwrap();
+            assert_eq!(walk(&triangles, &adjacency, guess, center), triangle.index);
+        }
+    }
+}
@@ -0,0 +1,597 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use bevy_math::primitives::Triangle3d;
+use bevy_math::Vec3;
+
+use crate::address::TriangleAddress;
+use crate::locate::{best_fit, polygon_containment};
+use crate::morph::Geomorph;
+use crate::geometry::{child_corners, edge_midpoint, Triangle};
+
+//settings controlling when triangles of the lod tree split and merge
+#[derive(Clone)]
+#[cfg_attr(feature = "app", derive(bevy::prelude::Resource))]
+pub struct LodSettings {
+    //if distance based lod is enabled, otherwise the sphere is subdivided uniformly
+    pub enabled: bool,
+    //deepest level a triangle can be split to
+    pub max_depth: usize,
+    //a triangle splits when a focus point is closer than this many edge lengths
+    pub split_distance: f32,
+    //a triangle merges its children when every focus point is further than this many edge lengths
+    //kept larger than split_distance so triangles on the boundary don't flicker between levels
+    pub merge_distance: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        LodSettings {
+            enabled: false,
+            max_depth: 7,
+            split_distance: 1.5,
+            merge_distance: 2.0,
+        }
+    }
+}
+
+//geometry of the nodes of a SplitTree, a triangle of a polyhedron or a quad of a cube face
+//the tree splits, merges, balances and morphs the nodes the same way whatever their shape
+pub trait NodeShape: Clone {
+    //corners counter clockwise seen from outside
+    fn corners(&self) -> &[Vec3];
+    //the four shapes a split produces, in the order of the children of the address
+    fn split(&self) -> [Self; 4];
+    //every vertex a split adds, with the two vertices it morphs in between and the number of children using it as a corner
+    fn new_vertices(&self) -> Vec<(Vec3, Vec3, Vec3, u32)>;
+    //vertices on the edges that only exist while a neighbour across an edge is at least two levels finer
+    fn quarter_points(&self) -> Vec<Vec3>;
+    //triangles a leaf is drawn as, given which vertices are corners of a leaf
+    fn triangles(&self, has_vertex: impl Fn(Vec3) -> bool) -> Vec<[Vec3; 3]>;
+}
+
+//a triangle splits into the four triangles made by subdivide, it is drawn as itself and stitch closes its t-junctions
+impl NodeShape for Triangle3d {
+    fn corners(&self) -> &[Vec3] {
+        &self.vertices
+    }
+
+    fn split(&self) -> [Self; 4] {
+        let [a, b, c] = self.vertices;
+        child_corners([a, b, c], [edge_midpoint(a, b), edge_midpoint(b, c), edge_midpoint(c, a)])
+            .map(|corners| Triangle3d::new(corners[0], corners[1], corners[2]))
+    }
+
+    //each midpoint is a corner of three of the children
+    fn new_vertices(&self) -> Vec<(Vec3, Vec3, Vec3, u32)> {
+        let [a, b, c] = self.vertices;
+        [(a, b), (b, c), (c, a)].into_iter().map(|(p, q)| (p, q, edge_midpoint(p, q), 3)).collect()
+    }
+
+    fn quarter_points(&self) -> Vec<Vec3> {
+        let [a, b, c] = self.vertices;
+        [(a, b), (b, c), (c, a)]
+            .into_iter()
+            .flat_map(|(p, q)| {
+                let mid = edge_midpoint(p, q);
+                [edge_midpoint(p, mid), edge_midpoint(mid, q)]
+            })
+            .collect()
+    }
+
+    fn triangles(&self, _: impl Fn(Vec3) -> bool) -> Vec<[Vec3; 3]> {
+        vec![self.vertices]
+    }
+}
+
+#[derive(Clone)]
+pub struct SplitNode<S> {
+    pub shape: S,
+    pub address: TriangleAddress,
+    //number of splits between this node and its base face
+    pub depth: usize,
+    pub parent: Option<usize>,
+    //the four nodes produced by NodeShape::split, in the same order
+    pub children: Option<[usize; 4]>,
+    //triangles of the leaf in the list returned by leaves, only meaningful for leaves
+    //kept up to date by update, so locate matches the next call to leaves
+    pub leaves: Range<usize>,
+}
+
+//hierarchical tree, each face of the base shape is a root and every node has either zero or four children
+//nodes split and merge by their distance to focus points, neighbours are kept at most one level apart
+#[derive(Clone)]
+pub struct SplitTree<S> {
+    pub nodes: Vec<SplitNode<S>>,
+    pub roots: Vec<usize>,
+    //slots of merged nodes that can be reused
+    free: Vec<usize>,
+    //number of leaves using each vertex as a corner, used to find finer neighbours
+    vertex_users: HashMap<VertexKey, u32>,
+    //set by every split, only a split can leave neighbours more than one level apart
+    unbalanced: bool,
+}
+
+//triangles of a polyhedron, the tree the geodesic sphere uses for its lod
+pub type LodTree = SplitTree<Triangle3d>;
+
+impl<S> Default for SplitTree<S> {
+    fn default() -> Self {
+        SplitTree {
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            free: Vec::new(),
+            vertex_users: HashMap::new(),
+            unbalanced: false,
+        }
+    }
+}
+
+//exact bit pattern of a vertex, midpoints are computed the same way on both sides of an edge so shared vertices match exactly
+pub type VertexKey = [u32; 3];
+
+pub fn vertex_key(vertex: Vec3) -> VertexKey {
+    vertex.to_array().map(f32::to_bits)
+}
+
+impl LodTree {
+    pub fn new(base: Vec<Triangle>) -> Self {
+        SplitTree::from_roots(base.into_iter().map(|triangle| (triangle.triangle, triangle.address)))
+    }
+}
+
+impl<S: NodeShape> SplitTree<S> {
+    pub fn from_roots(roots: impl IntoIterator<Item = (S, TriangleAddress)>) -> Self {
+        let mut tree = SplitTree::default();
+        for (shape, address) in roots {
+            let root = tree.alloc(SplitNode {
+                shape,
+                address,
+                depth: 0,
+                parent: None,
+                children: None,
+                leaves: 0..0,
+            });
+            tree.roots.push(root);
+            tree.add_corners(root);
+        }
+        tree.index_leaves();
+        tree
+    }
+
+    //true if any leaf has this vertex as a corner
+    pub fn has_vertex(&self, vertex: Vec3) -> bool {
+        self.vertex_users.contains_key(&vertex_key(vertex))
+    }
+
+    pub fn is_leaf(&self, node: usize) -> bool {
+        self.nodes[node].children.is_none()
+    }
+
+    //collects the triangles of the leaves, these are the triangles that get rendered
+    //corners shared between leaves are welded into a single vertex buffer
+    //a leaf drawn as several triangles gives each of them its address with the piece set, so every address stays unique
+    pub fn leaves(&mut self) -> (Vec<Vec3>, Vec<Triangle>) {
+        let mut vertices: Vec<Vec3> = Vec::new();
+        let mut vertex_ids: HashMap<VertexKey, u32> = HashMap::new();
+        let mut leaves: Vec<Triangle> = Vec::new();
+
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+        while let Some(node) = stack.pop() {
+            if let Some(children) = self.nodes[node].children {
+                stack.extend(children.iter().rev());
+                continue;
+            }
+            let start = leaves.len();
+            let triangles = self.leaf_triangles(node);
+            let pieces = triangles.len();
+            for (piece, [a, b, c]) in triangles.into_iter().enumerate() {
+                let corners = [a, b, c].map(|vertex| {
+                    *vertex_ids.entry(vertex_key(vertex)).or_insert_with(|| {
+                        vertices.push(vertex);
+                        (vertices.len() - 1) as u32
+                    })
+                });
+                let address = &self.nodes[node].address;
+                leaves.push(Triangle {
+                    index: leaves.len(),
+                    triangle: Triangle3d::new(a, b, c),
+                    corners,
+                    address: if pieces > 1 { address.piece(piece as u8) } else { address.clone() },
+                });
+            }
+            self.nodes[node].leaves = start..leaves.len();
+        }
+        (vertices, leaves)
+    }
+
+    //records where the triangles of every leaf end up in the list returned by leaves, in the same order, so locate can return them
+    //the fans of a leaf depend on its neighbours, so every leaf is counted again
+    fn index_leaves(&mut self) {
+        let mut start = 0;
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+        while let Some(node) = stack.pop() {
+            if let Some(children) = self.nodes[node].children {
+                stack.extend(children.iter().rev());
+                continue;
+            }
+            let end = start + self.leaf_triangles(node).len();
+            self.nodes[node].leaves = start..end;
+            start = end;
+        }
+    }
+
+    fn leaf_triangles(&self, node: usize) -> Vec<[Vec3; 3]> {
+        self.nodes[node].shape.triangles(|vertex| self.has_vertex(vertex))
+    }
+
+    //index of the triangle containing a point on the unit sphere, following the tree down from the base faces
+    pub fn locate(&self, point: Vec3) -> Option<usize> {
+        let best = |nodes: &[usize]| {
+            nodes
+                .iter()
+                .copied()
+                .max_by(|&a, &b| polygon_containment(self.nodes[a].shape.corners(), point).total_cmp(&polygon_containment(self.nodes[b].shape.corners(), point)))
+        };
+        let mut node = best(&self.roots)?;
+        while let Some(children) = self.nodes[node].children {
+            node = best(&children)?;
+        }
+        //the point can sit on any of the triangles of the leaf, or just outside it near its edge
+        let leaves = self.nodes[node].leaves.clone();
+        if leaves.len() == 1 {
+            return Some(leaves.start);
+        }
+        let triangles: Vec<Triangle3d> = self.leaf_triangles(node).into_iter().map(|[a, b, c]| Triangle3d::new(a, b, c)).collect();
+        Some(leaves.start + best_fit(&triangles, point)?)
+    }
+
+    //splits or merges nodes based on their distance to the focus points
+    //the focus points move a node at most one level per call so the work is spread over several frames
+    //a split can still cascade over several levels while balance refines its neighbours
+    //new vertices are morphed in, and merges wait until the vertices they remove have morphed out
+    //returns true if the set of leaves changed
+    pub fn update(&mut self, focus: &[Vec3], min_depth: usize, settings: &LodSettings, geomorph: &mut Geomorph) -> bool {
+        let mut changed = false;
+        for i in 0..self.roots.len() {
+            changed |= self.update_node(self.roots[i], focus, min_depth, settings, geomorph);
+        }
+        if self.unbalanced {
+            changed |= self.balance(geomorph);
+        }
+        if changed {
+            self.index_leaves();
+        }
+        changed
+    }
+
+    //splits leaves until no two leaves sharing an edge are more than one level apart
+    //this keeps every t-junction down to a single midpoint, which stitch and the fans of the cube quads can close
+    fn balance(&mut self, geomorph: &mut Geomorph) -> bool {
+        let mut changed = false;
+        loop {
+            let unbalanced: Vec<usize> = self
+                .leaf_nodes()
+                .into_iter()
+                .filter(|&leaf| self.has_deep_neighbour(leaf))
+                .collect();
+            if unbalanced.is_empty() {
+                self.unbalanced = false;
+                return changed;
+            }
+            for leaf in unbalanced {
+                self.split(leaf, geomorph);
+            }
+            changed = true;
+        }
+    }
+
+    //true if a neighbour across one of the edges of the node is at least two levels finer than the node
+    fn has_deep_neighbour(&self, node: usize) -> bool {
+        self.nodes[node].shape.quarter_points().into_iter().any(|vertex| self.has_vertex(vertex))
+    }
+
+    //merging is only allowed if no neighbour of the children is finer than the children themselves
+    fn can_merge(&self, node: usize) -> bool {
+        let Some(children) = self.nodes[node].children else {
+            return false;
+        };
+        children.iter().all(|&child| self.is_leaf(child)) && !self.has_deep_neighbour(node)
+    }
+
+    fn leaf_nodes(&self) -> Vec<usize> {
+        let mut leaves = Vec::new();
+        let mut stack: Vec<usize> = self.roots.clone();
+        while let Some(node) = stack.pop() {
+            match self.nodes[node].children {
+                Some(children) => stack.extend(children),
+                None => leaves.push(node),
+            }
+        }
+        leaves
+    }
+
+    fn update_node(&mut self, node: usize, focus: &[Vec3], min_depth: usize, settings: &LodSettings, geomorph: &mut Geomorph) -> bool {
+        let depth = self.nodes[node].depth;
+        let max_depth = settings.max_depth.max(min_depth);
+        let distance = self.focus_distance(node, focus);
+
+        match self.nodes[node].children {
+            None => {
+                if depth < min_depth || (depth < max_depth && distance < settings.split_distance) {
+                    self.split(node, geomorph);
+                    return true;
+                }
+                false
+            }
+            Some(children) => {
+                if self.can_merge(node) && depth >= min_depth && (depth >= max_depth || distance > settings.merge_distance) {
+                    if self.morph_out_children(node, geomorph) {
+                        self.merge(node, geomorph);
+                        return true;
+                    }
+                    return false;
+                }
+                //the node is staying split, bring back any vertices that started morphing out
+                for (_, _, vertex, _) in self.nodes[node].shape.new_vertices() {
+                    geomorph.cancel_morph_out(vertex);
+                }
+                let mut changed = false;
+                for child in children {
+                    changed |= self.update_node(child, focus, min_depth, settings, geomorph);
+                }
+                changed
+            }
+        }
+    }
+
+    //distance from the closest focus point to the node, measured in edge lengths of the node
+    fn focus_distance(&self, node: usize, focus: &[Vec3]) -> f32 {
+        let corners = self.nodes[node].shape.corners();
+        let centroid = corners.iter().sum::<Vec3>() / corners.len() as f32;
+        let size = corners[0].distance(corners[1]);
+        let radius = corners.iter().map(|v| v.distance(centroid)).fold(0.0, f32::max);
+
+        let mut closest = f32::INFINITY;
+        for point in focus {
+            closest = closest.min((point.distance(centroid) - radius).max(0.0));
+        }
+        closest / size
+    }
+
+    //morphs the vertices that disappear when the node merges back between the vertices they were made from
+    //vertices that are also corners of a split neighbour stay where they are
+    //returns true once all of them have arrived
+    fn morph_out_children(&self, node: usize, geomorph: &mut Geomorph) -> bool {
+        let mut done = true;
+        for (a, b, vertex, own_users) in self.nodes[node].shape.new_vertices() {
+            if self.vertex_users.get(&vertex_key(vertex)).copied().unwrap_or(0) > own_users {
+                geomorph.cancel_morph_out(vertex);
+            } else {
+                done &= geomorph.morph_out(vertex, a, b);
+            }
+        }
+        done
+    }
+
+    fn split(&mut self, node: usize, geomorph: &mut Geomorph) {
+        //morph in the vertices that are not already shared with a split neighbour
+        for (a, b, vertex, _) in self.nodes[node].shape.new_vertices() {
+            if self.has_vertex(vertex) {
+                geomorph.cancel_morph_out(vertex);
+            } else {
+                geomorph.morph_in(vertex, a, b);
+            }
+        }
+
+        let depth = self.nodes[node].depth;
+        let mut children = [0; 4];
+        for (i, shape) in self.nodes[node].shape.split().into_iter().enumerate() {
+            children[i] = self.alloc(SplitNode {
+                shape,
+                address: self.nodes[node].address.child(i as u8),
+                depth: depth + 1,
+                parent: Some(node),
+                children: None,
+                leaves: 0..0,
+            });
+            self.add_corners(children[i]);
+        }
+        self.remove_corners(node);
+        self.nodes[node].children = Some(children);
+        self.unbalanced = true;
+    }
+
+    fn merge(&mut self, node: usize, geomorph: &mut Geomorph) {
+        if let Some(children) = self.nodes[node].children.take() {
+            for child in children {
+                self.merge(child, geomorph);
+                self.remove_corners(child);
+                self.free.push(child);
+            }
+            self.add_corners(node);
+
+            for (_, _, vertex, _) in self.nodes[node].shape.new_vertices() {
+                if !self.has_vertex(vertex) {
+                    geomorph.remove(vertex);
+                }
+            }
+        }
+    }
+
+    fn add_corners(&mut self, node: usize) {
+        for &vertex in self.nodes[node].shape.corners() {
+            *self.vertex_users.entry(vertex_key(vertex)).or_insert(0) += 1;
+        }
+    }
+
+    fn remove_corners(&mut self, node: usize) {
+        for &vertex in self.nodes[node].shape.corners() {
+            let key = vertex_key(vertex);
+            if let Some(users) = self.vertex_users.get_mut(&key) {
+                *users -= 1;
+                if *users == 0 {
+                    self.vertex_users.remove(&key);
+                }
+            }
+        }
+    }
+
+    fn alloc(&mut self, node: SplitNode<S>) -> usize {
+        match self.free.pop() {
+            Some(slot) => {
+                self.nodes[slot] = node;
+                slot
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+}
+
+//closes the t-junctions between leaves of different depth
+//an edge whose midpoint is a corner of a neighbouring triangle is split at that midpoint, and the triangle is fanned around it
+//returns the corners of the mesh triangles in the shared vertex buffer, and which of them each triangle was drawn as
+//expects neighbours to be at most one level apart, which LodTree::update guarantees
+pub fn stitch(vertices: &[Vec3], triangles: &[Triangle]) -> (Vec<[u32; 3]>, Vec<Range<usize>>) {
+    let vertex_ids: HashMap<VertexKey, u32> = vertices
+        .iter()
+        .enumerate()
+        .map(|(id, &vertex)| (vertex_key(vertex), id as u32))
+        .collect();
+
+    let mut stitched = Vec::with_capacity(triangles.len());
+    let mut pieces = Vec::with_capacity(triangles.len());
+    for triangle in triangles {
+        let start = stitched.len();
+        let v = triangle.corners;
+        let mids: Vec<Option<u32>> = (0..3)
+            .map(|i| {
+                let mid = edge_midpoint(vertices[v[i] as usize], vertices[v[(i + 1) % 3] as usize]);
+                vertex_ids.get(&vertex_key(mid)).copied()
+            })
+            .collect();
+
+        match mids.iter().filter(|mid| mid.is_some()).count() {
+            0 => stitched.push(v),
+            1 => {
+                //rotate so the split edge goes from a to b
+                let r = mids.iter().position(|mid| mid.is_some()).unwrap();
+                let (a, b, c) = (v[r], v[(r + 1) % 3], v[(r + 2) % 3]);
+                let m = mids[r].unwrap();
+                stitched.push([a, m, c]);
+                stitched.push([m, b, c]);
+            }
+            2 => {
+                //rotate so the only unsplit edge goes from c to a
+                let r = (mids.iter().position(|mid| mid.is_none()).unwrap() + 1) % 3;
+                let (a, b, c) = (v[r], v[(r + 1) % 3], v[(r + 2) % 3]);
+                let ab = mids[r].unwrap();
+                let bc = mids[(r + 1) % 3].unwrap();
+                stitched.push([a, ab, bc]);
+                stitched.push([ab, b, bc]);
+                stitched.push([a, bc, c]);
+            }
+            _ => {
+                //same split as subdivide
+                let mids = [mids[0].unwrap(), mids[1].unwrap(), mids[2].unwrap()];
+                stitched.extend(child_corners(v, mids));
+            }
+        }
+        pieces.push(start..stitched.len());
+    }
+    (stitched, pieces)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use bevy_math::Quat;
+
+    use super::*;
+    use crate::geometry::icosahedron;
+
+    //small distances give steep level changes, so balancing has work to do
+    pub(crate) fn settings() -> LodSettings {
+        LodSettings {
+            enabled: true,
+            max_depth: 6,
+            split_distance: 0.1,
+            merge_distance: 0.2,
+        }
+    }
+
+    //checks that every edge of the mesh triangles is used by exactly two of them
+    pub(crate) fn assert_watertight(mesh: &[[u32; 3]]) {
+        let mut counts: HashMap<(u32, u32), u32> = HashMap::new();
+        for v in mesh {
+            for i in 0..3 {
+                let (a, b) = (v[i], v[(i + 1) % 3]);
+                *counts.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        for (edge, count) in counts {
+            assert_eq!(count, 2, "edge {:?} is used by {} triangles", edge, count);
+        }
+    }
+
+    fn assert_stitched_watertight(tree: &mut LodTree) {
+        let (vertices, triangles) = tree.leaves();
+        assert_watertight(&stitch(&vertices, &triangles).0);
+    }
+
+    #[test]
+    fn stitched_mesh_is_watertight() {
+        let mut tree = LodTree::new(icosahedron().1);
+        let focus = [Vec3::new(0.3, 0.2, 1.0).normalize()];
+        while tree.update(&focus, 1, &settings(), &mut Geomorph::instant()) {}
+
+        //make sure there actually are several levels to stitch
+        let depths: Vec<usize> = tree.leaf_nodes().iter().map(|&leaf| tree.nodes[leaf].depth).collect();
+        assert!(depths.iter().max().unwrap() - depths.iter().min().unwrap() >= 3);
+
+        assert_stitched_watertight(&mut tree);
+    }
+
+    #[test]
+    fn lod_tree_locates_every_leaf() {
+        let mut tree = LodTree::new(icosahedron().1);
+        let focus = [Vec3::new(-0.4, 0.8, 0.1).normalize()];
+        while tree.update(&focus, 1, &settings(), &mut Geomorph::instant()) {}
+        let (_, leaves) = tree.leaves();
+        assert!(leaves.len() > 20 * 4);
+        for leaf in leaves {
+            let center = leaf.triangle.centroid().normalize();
+            assert_eq!(tree.locate(center), Some(leaf.index));
+        }
+    }
+
+    #[test]
+    fn locate_is_current_straight_after_update() {
+        let mut tree = LodTree::new(icosahedron().1);
+        let mut focus = Vec3::Z;
+        for _ in 0..20 {
+            focus = Quat::from_rotation_x(0.15).mul_vec3(focus);
+            if !tree.update(&[focus], 1, &settings(), &mut Geomorph::instant()) {
+                continue;
+            }
+            //leaves is called on a copy, so only update has touched the tree that is searched
+            let (_, leaves) = tree.clone().leaves();
+            for leaf in leaves {
+                assert_eq!(tree.locate(leaf.triangle.centroid().normalize()), Some(leaf.index));
+            }
+        }
+    }
+
+    #[test]
+    fn stitched_mesh_stays_watertight_while_focus_moves() {
+        let mut tree = LodTree::new(icosahedron().1);
+        let mut focus = Vec3::Z;
+        for _ in 0..40 {
+            focus = Quat::from_rotation_y(0.1).mul_vec3(focus);
+            while tree.update(&[focus], 0, &settings(), &mut Geomorph::instant()) {}
+            assert_stitched_watertight(&mut tree);
+        }
+    }
+}
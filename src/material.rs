@@ -0,0 +1,224 @@
+use bevy::ecs::system::SystemParam;
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{AsBindGroup, Extent3d, ShaderRef, TextureDimension, TextureFormat};
+use bevy::render::renderer::RenderDevice;
+use bevy::render::settings::WgpuFeatures;
+
+use crate::character::CharacterState;
+use crate::sphere::{Sphere, SphereState};
+
+//texels per row of the triangle textures, well below the smallest texture size limit
+const TEXTURE_WIDTH: usize = 2048;
+
+//standard material of the sphere, tinted per triangle by the textures of TriangleData
+pub type TriangleMaterial = ExtendedMaterial<StandardMaterial, TriangleData>;
+
+//per triangle data read by the shader, so overlays and highlights don't need new vertex colors
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+pub struct TriangleData {
+    //triangle drawn as each primitive of the mesh, see generate_triangle_index_texture
+    #[texture(100, sample_type = "u_int")]
+    pub index: Handle<Image>,
+    //color every triangle is tinted with, see generate_triangle_data_texture
+    #[texture(101)]
+    pub data: Handle<Image>,
+    //triangle drawn in the highlight color instead of its vertex colors and tint, u32::MAX for none
+    #[uniform(102)]
+    pub highlight: u32,
+    #[uniform(103)]
+    pub highlight_color: LinearRgba,
+}
+
+impl MaterialExtension for TriangleData {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/triangle_data.wgsl".into()
+    }
+}
+
+//what the triangle textures show
+#[derive(Resource, Clone, Default, PartialEq, Debug)]
+pub struct TriangleOverlay {
+    //layer tinting the triangles, it needs a color function, see TriangleLayer::with_colors
+    pub layer: Option<String>,
+    //color of the triangle under the character, None to leave it as it is
+    pub highlight: Option<Color>,
+}
+
+//draws the sphere with TriangleMaterial, tinted by TriangleOverlay on the gpu
+//the shader needs the primitive index, on devices without it the sphere keeps its standard material
+pub struct TriangleDataPlugin;
+
+impl Plugin for TriangleDataPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<TriangleMaterial>::default())
+            .init_resource::<TriangleOverlay>()
+            .add_systems(Update, (use_triangle_material, update_triangle_data).chain().after(crate::colors::update_colors));
+    }
+}
+
+//size of a texture holding one texel per entry, filled row by row
+fn texture_size(entries: usize) -> Extent3d {
+    Extent3d {
+        width: TEXTURE_WIDTH as u32,
+        height: entries.div_ceil(TEXTURE_WIDTH).max(1) as u32,
+        depth_or_array_layers: 1,
+    }
+}
+
+//R32Uint texture of the triangle every primitive of the sphere mesh belongs to
+//the mesh draws some triangles as several primitives along lod seams, see SphereState::pieces
+pub fn generate_triangle_index_texture(sphere_state: &SphereState) -> Image {
+    let primitives = sphere_state.indices.len() / 3;
+    let size = texture_size(primitives);
+    let mut texels = vec![u32::MAX; size.width as usize * size.height as usize];
+    for (triangle, pieces) in sphere_state.pieces.iter().enumerate() {
+        for piece in pieces.clone() {
+            texels[piece] = triangle as u32;
+        }
+    }
+    Image::new(size, TextureDimension::D2, texels.into_iter().flat_map(u32::to_le_bytes).collect(), TextureFormat::R32Uint, RenderAssetUsages::default())
+}
+
+//Rgba8Unorm texture of one linear color per triangle
+pub fn generate_triangle_data_texture(colors: &[[f32; 4]]) -> Image {
+    let size = texture_size(colors.len());
+    let mut data = vec![255; size.width as usize * size.height as usize * 4];
+    for (texel, color) in data.chunks_exact_mut(4).zip(colors) {
+        for (byte, channel) in texel.iter_mut().zip(color) {
+            *byte = (channel.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+    Image::new(size, TextureDimension::D2, data, TextureFormat::Rgba8Unorm, RenderAssetUsages::default())
+}
+
+//tint of every triangle, white unless the overlay layer colors it
+fn overlay_colors(sphere_state: &SphereState, overlay: &TriangleOverlay) -> Vec<[f32; 4]> {
+    overlay
+        .layer
+        .as_deref()
+        .and_then(|name| sphere_state.layers.triangle_colors(name))
+        .unwrap_or_else(|| vec![[1.0; 4]; sphere_state.triangles.len()])
+}
+
+//the textures of the triangle materials together with what they are written from
+#[derive(SystemParam)]
+pub struct TriangleTextures<'w> {
+    pub images: ResMut<'w, Assets<Image>>,
+    pub sphere_state: Res<'w, SphereState>,
+    pub overlay: Res<'w, TriangleOverlay>,
+}
+
+impl TriangleTextures<'_> {
+    //new index and data textures of the current triangles and overlay
+    pub fn add(&mut self) -> (Handle<Image>, Handle<Image>) {
+        let index = self.images.add(generate_triangle_index_texture(&self.sphere_state));
+        let data = self.images.add(generate_triangle_data_texture(&overlay_colors(&self.sphere_state, &self.overlay)));
+        (index, data)
+    }
+
+    //rewrites the textures with the current triangles and overlay
+    pub fn write(&mut self, index: &Handle<Image>, data: &Handle<Image>) {
+        self.images.insert(index, generate_triangle_index_texture(&self.sphere_state));
+        self.images.insert(data, generate_triangle_data_texture(&overlay_colors(&self.sphere_state, &self.overlay)));
+    }
+}
+
+//swaps the standard material of newly spawned spheres for a TriangleMaterial with the same settings
+pub fn use_triangle_material(
+    mut commands: Commands,
+    sphere_query: Query<(Entity, &Handle<StandardMaterial>), With<Sphere>>,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut triangle_materials: ResMut<Assets<TriangleMaterial>>,
+    mut textures: TriangleTextures,
+    render_device: Option<Res<RenderDevice>>,
+    mut warned: Local<bool>,
+) {
+    if sphere_query.is_empty() {
+        return;
+    }
+    if !render_device.is_some_and(|device| device.features().contains(WgpuFeatures::SHADER_PRIMITIVE_INDEX)) {
+        if !*warned {
+            warn!("the device doesn't support primitive indices, the sphere keeps its standard material");
+            *warned = true;
+        }
+        return;
+    }
+
+    for (entity, standard) in &sphere_query {
+        let (index, data) = textures.add();
+        let material = TriangleMaterial {
+            base: standard_materials.get(standard).cloned().unwrap_or_default(),
+            extension: TriangleData {
+                index,
+                data,
+                highlight: u32::MAX,
+                highlight_color: LinearRgba::WHITE,
+            },
+        };
+        commands.entity(entity).remove::<Handle<StandardMaterial>>().insert(triangle_materials.add(material));
+    }
+}
+
+//rewrites the textures when the triangles or the overlay layer changed, and moves the highlight along with the character
+pub fn update_triangle_data(
+    sphere_query: Query<&Handle<TriangleMaterial>, With<Sphere>>,
+    mut triangle_materials: ResMut<Assets<TriangleMaterial>>,
+    mut textures: TriangleTextures,
+    character_state: Res<CharacterState>,
+    //triangles and overlay version the textures were written for
+    mut written: Local<Option<(u64, Option<u64>)>>,
+) {
+    let (sphere_state, overlay) = (&textures.sphere_state, &textures.overlay);
+    let layer = overlay.layer.as_deref().and_then(|name| sphere_state.layers.color_version(name));
+    let current = (sphere_state.generation, layer);
+    let rewrite = *written != Some(current) || overlay.is_changed();
+    let highlight = match overlay.highlight {
+        Some(color) => (character_state.current_triangle_id as u32, color.to_linear()),
+        None => (u32::MAX, LinearRgba::WHITE),
+    };
+
+    for handle in &sphere_query {
+        let Some(material) = triangle_materials.get(handle) else {
+            continue;
+        };
+        let (index, data) = (material.extension.index.clone(), material.extension.data.clone());
+        //the material is only touched when the highlight moved, touching it prepares it again
+        if (material.extension.highlight, material.extension.highlight_color) != highlight {
+            if let Some(material) = triangle_materials.get_mut(handle) {
+                (material.extension.highlight, material.extension.highlight_color) = highlight;
+            }
+        }
+        if rewrite {
+            textures.write(&index, &data);
+        }
+    }
+    *written = Some(current);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lod::tests::settings;
+
+    #[test]
+    fn index_texture_maps_every_primitive_to_its_triangle() {
+        //a lod sphere, so some triangles are drawn as several primitives
+        let sphere_state = SphereState::lod(1, &settings(), &[Vec3::Z]);
+        assert!(sphere_state.pieces.iter().any(|pieces| pieces.len() > 1));
+
+        let image = generate_triangle_index_texture(&sphere_state);
+        assert_eq!(image.texture_descriptor.format, TextureFormat::R32Uint);
+        let texel = |i: usize| u32::from_le_bytes(image.data[i * 4..i * 4 + 4].try_into().unwrap());
+        for (triangle, pieces) in sphere_state.pieces.iter().enumerate() {
+            for piece in pieces.clone() {
+                assert_eq!(texel(piece), triangle as u32);
+            }
+        }
+
+        let image = generate_triangle_data_texture(&[[1.0, 0.5, 0.0, 1.0], [0.0, 0.0, 1.0, 0.25]]);
+        assert_eq!(image.texture_descriptor.format, TextureFormat::Rgba8Unorm);
+        assert_eq!(&image.data[..8], &[255, 128, 0, 255, 0, 0, 255, 64]);
+    }
+}
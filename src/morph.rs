@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use bevy_math::Vec3;
+
+use crate::height::Heightmap;
+use crate::lod::{vertex_key, VertexKey};
+
+//state of a single vertex that is being blended between its parent edge and its final position
+#[derive(Clone, Copy)]
+pub struct VertexMorph {
+    //endpoints of the edge the vertex was created on by subdivide
+    pub parents: (Vec3, Vec3),
+    //0 at the midpoint of the parent edge, 1 at the final position
+    pub factor: f32,
+    //1 while the vertex grows in after a split, -1 while it shrinks back before a merge
+    pub direction: f32,
+}
+
+//geomorphing of the sphere surface, removes the popping when triangles split or merge
+//vertices are keyed by their final position so both sides of a stitched edge share the same morph
+#[derive(Clone)]
+#[cfg_attr(feature = "app", derive(bevy::prelude::Resource))]
+pub struct Geomorph {
+    //seconds a vertex takes to morph in or out, 0 disables morphing
+    pub duration: f32,
+    pub vertices: HashMap<VertexKey, VertexMorph>,
+}
+
+impl Default for Geomorph {
+    fn default() -> Self {
+        Geomorph {
+            duration: 0.4,
+            vertices: HashMap::new(),
+        }
+    }
+}
+
+impl Geomorph {
+    //geomorph that doesn't morph, vertices jump straight to their final position
+    pub fn instant() -> Self {
+        Geomorph {
+            duration: 0.0,
+            vertices: HashMap::new(),
+        }
+    }
+
+    //starts morphing a newly created midpoint out from its parent edge
+    pub fn morph_in(&mut self, vertex: Vec3, a: Vec3, b: Vec3) {
+        if self.duration <= 0.0 {
+            return;
+        }
+        self.vertices
+            .entry(vertex_key(vertex))
+            .and_modify(|morph| morph.direction = 1.0)
+            .or_insert(VertexMorph {
+                parents: (a, b),
+                factor: 0.0,
+                direction: 1.0,
+            });
+    }
+
+    //starts morphing a midpoint back onto its parent edge, returns true once it got there
+    pub fn morph_out(&mut self, vertex: Vec3, a: Vec3, b: Vec3) -> bool {
+        if self.duration <= 0.0 {
+            return true;
+        }
+        let morph = self.vertices.entry(vertex_key(vertex)).or_insert(VertexMorph {
+            parents: (a, b),
+            factor: 1.0,
+            direction: -1.0,
+        });
+        morph.direction = -1.0;
+        morph.factor <= 0.0
+    }
+
+    //turns a vertex that is morphing out around, vertices that are not morphing are left alone
+    pub fn cancel_morph_out(&mut self, vertex: Vec3) {
+        if let Some(morph) = self.vertices.get_mut(&vertex_key(vertex)) {
+            morph.direction = 1.0;
+        }
+    }
+
+    //forgets a vertex, used once it is no longer part of the mesh
+    pub fn remove(&mut self, vertex: Vec3) {
+        self.vertices.remove(&vertex_key(vertex));
+    }
+
+    //true if some vertex is still on its way back to its parent edge
+    pub fn is_morphing_out(&self) -> bool {
+        self.vertices.values().any(|morph| morph.direction < 0.0 && morph.factor > 0.0)
+    }
+
+    //advances every morph, returns true if any vertex moved
+    //vertices that finished morphing in are dropped, vertices that finished morphing out wait at their parent edge until removed
+    pub fn advance(&mut self, dt: f32) -> bool {
+        if self.vertices.is_empty() {
+            return false;
+        }
+        let step = dt / self.duration;
+        let mut moved = false;
+        for morph in self.vertices.values_mut() {
+            let factor = (morph.factor + morph.direction * step).clamp(0.0, 1.0);
+            moved |= factor != morph.factor;
+            morph.factor = factor;
+        }
+        self.vertices.retain(|_, morph| morph.direction < 0.0 || morph.factor < 1.0);
+        moved
+    }
+
+    //position a vertex is rendered at, on the terrain once it finished morphing
+    //parents can be morphing themselves when several levels split in quick succession
+    pub fn position(&self, vertex: Vec3, heightmap: &Heightmap) -> Vec3 {
+        match self.vertices.get(&vertex_key(vertex)) {
+            Some(morph) => {
+                let origin = self.position(morph.parents.0, heightmap).midpoint(self.position(morph.parents.1, heightmap));
+                let t = morph.factor * morph.factor * (3.0 - 2.0 * morph.factor);
+                origin.lerp(heightmap.displace(vertex), t)
+            }
+            None => heightmap.displace(vertex),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::edge_midpoint;
+
+    #[test]
+    fn vertices_morph_between_their_parent_edge_and_their_position() {
+        let (a, b) = (Vec3::X, Vec3::Y);
+        let mid = edge_midpoint(a, b);
+        let heightmap = Heightmap::Flat;
+        let mut geomorph = Geomorph::default();
+
+        //morphing in starts on the parent edge and ends at the vertex
+        geomorph.morph_in(mid, a, b);
+        assert!(geomorph.position(mid, &heightmap).distance(a.midpoint(b)) < 1e-6);
+        assert!(geomorph.advance(geomorph.duration * 0.5));
+        assert!(geomorph.advance(geomorph.duration * 0.6));
+        assert!(geomorph.vertices.is_empty());
+        assert_eq!(geomorph.position(mid, &heightmap), mid);
+
+        //morphing out holds until the factor reaches 0
+        assert!(!geomorph.morph_out(mid, a, b));
+        assert!(geomorph.is_morphing_out());
+        geomorph.advance(geomorph.duration * 0.5);
+        assert!(geomorph.is_morphing_out());
+        assert!(!geomorph.morph_out(mid, a, b));
+        geomorph.advance(geomorph.duration * 0.6);
+        assert!(!geomorph.is_morphing_out());
+        assert!(geomorph.morph_out(mid, a, b));
+        assert!(geomorph.position(mid, &heightmap).distance(a.midpoint(b)) < 1e-6);
+
+        //cancelling halfway brings the vertex back to its position
+        let mut geomorph = Geomorph::default();
+        geomorph.morph_out(mid, a, b);
+        geomorph.advance(geomorph.duration * 0.5);
+        geomorph.cancel_morph_out(mid);
+        assert!(!geomorph.is_morphing_out());
+        geomorph.advance(geomorph.duration);
+        assert!(geomorph.vertices.is_empty());
+        assert_eq!(geomorph.position(mid, &heightmap), mid);
+    }
+}
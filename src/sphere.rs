@@ -0,0 +1,546 @@
+use std::ops::Range;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use bevy::pbr::wireframe::Wireframe;
+use bevy::render::mesh::{Indices, MeshVertexAttribute, PrimitiveTopology, VertexAttributeValues};
+use bevy::render::render_asset::RenderAssetUsages;
+
+use crate::adjacency::TriangleAdjacency;
+use crate::cells::CellGrid;
+use crate::character::CharacterState;
+use crate::cube::{CubeWarp, QuadTree};
+use crate::geometry::{edge_midpoint, flat_normals, frequency_subdivide, smooth_normals, uniform_levels, uniform_subdivide, Placement, Polyhedron, Triangle};
+use crate::height::Heightmap;
+use crate::layer::{LayerValue, Resampling, TriangleLayer, TriangleLayers};
+use crate::locate::{containment, locate_frequency, locate_uniform, walk, TriangleLocator};
+use crate::lod::{stitch, LodSettings, LodTree};
+use crate::morph::Geomorph;
+use crate::LodCamera;
+
+//marks the entity rendering the sphere mesh
+#[derive(Component)]
+pub struct Sphere;
+
+//subdivision level of the sphere, the minimum depth of the lod tree when lod is enabled
+#[derive(Resource)]
+pub struct Subdivisions {
+    pub value: usize,
+    //what the value counts
+    pub mode: SubdivisionMode,
+    //where the vertices of the uniform sphere are put, in either mode
+    pub placement: Placement,
+}
+
+//how the uniform sphere is subdivided
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum SubdivisionMode {
+    //value is the number of times every triangle is split into 4, the triangle count grows 4 times per step
+    #[default]
+    Midpoint,
+    //value is the number of segments every base edge is split into, so the triangle count grows with its square
+    //the lod and cube trees only split in halves, they use the finest midpoint level that isn't finer than the frequency
+    Frequency,
+}
+
+//how the sphere mesh is shaded
+#[derive(Resource, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum NormalMode {
+    //every triangle is lit as a flat facet, the mesh gets its own copy of every vertex per triangle
+    Flat,
+    //normals are averaged over the triangles around each shared vertex
+    #[default]
+    Smooth,
+}
+
+//polyhedron the sphere is built from
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BaseShape {
+    //triangles of a polyhedron, split into 4 triangles each
+    Polyhedron(Polyhedron),
+    //6 cube faces projected onto the sphere, split into 4 quads each
+    //warp picks how the face grids are laid onto the sphere, see CubeWarp
+    CubeSphere { warp: CubeWarp },
+}
+
+impl Default for BaseShape {
+    fn default() -> Self {
+        BaseShape::Polyhedron(Polyhedron::Icosahedron)
+    }
+}
+
+//global state of sphere, so modification of the number of subdivisions can be done without losing the current state of the sphere
+#[derive(Resource, Clone)]
+pub struct SphereState {
+    pub wireframe: bool,
+    //shared vertex buffer, the corners of the triangles index into this
+    pub vertices: Vec<Vec3>,
+    //list of triangles
+    pub triangles: Vec<Triangle>,
+    //index buffer of the mesh, the triangles stitched along lod seams
+    pub indices: Vec<u32>,
+    //mesh triangles each triangle is drawn as, ranges of triangles in the index buffer
+    //a triangle fanned around a t-junction is drawn as more than one
+    pub pieces: Vec<Range<usize>>,
+    //neighbours of the triangles, rebuilt together with them
+    pub adjacency: TriangleAdjacency,
+    //hexagonal and pentagonal cells around the shared vertices, rebuilt together with the triangles
+    pub cells: CellGrid,
+    //data stored per triangle, resampled whenever the triangles are replaced
+    pub layers: TriangleLayers,
+    //finds the triangle under a point without looking at every triangle
+    pub locator: TriangleLocator,
+    //handle to the mesh
+    pub mesh: Handle<Mesh>,
+    //triangle tree used when lod is enabled, its leaves are the triangles above
+    pub lod: LodTree,
+    //quad tree used by the cube sphere, with or without lod
+    pub cube: QuadTree,
+    //subdivision level of the uniform triangles, lags behind Subdivisions while a coarser level morphs out
+    pub subdivisions: usize,
+    //polyhedron the triangles were grown from
+    pub base: Polyhedron,
+    //counts the times the triangles were replaced, so data drawn from them knows when to update
+    pub generation: u64,
+}
+
+impl Default for Subdivisions {
+    fn default() -> Self {
+        Subdivisions { value: 0, mode: SubdivisionMode::Midpoint, placement: Placement::Normalized }
+    }
+}
+
+impl Subdivisions {
+    //number of midpoint subdivisions, for the trees and the morphing uniform sphere
+    pub fn depth(&self) -> usize {
+        match self.mode {
+            SubdivisionMode::Midpoint => self.value,
+            SubdivisionMode::Frequency => self.frequency().ilog2() as usize,
+        }
+    }
+
+    //number of segments each base edge is split into
+    pub fn frequency(&self) -> usize {
+        match self.mode {
+            SubdivisionMode::Midpoint => 1 << self.value,
+            SubdivisionMode::Frequency => self.value.max(1),
+        }
+    }
+
+    //switches the mode, the value is converted so the sphere keeps about the same number of triangles
+    pub fn set_mode(&mut self, mode: SubdivisionMode) {
+        self.value = match mode {
+            SubdivisionMode::Midpoint => self.depth(),
+            SubdivisionMode::Frequency => self.frequency(),
+        };
+        self.mode = mode;
+    }
+}
+
+//resources the app changes to reshape the sphere, everything building it reads them from here
+#[derive(SystemParam)]
+pub struct SphereSettings<'w> {
+    pub subdivisions: Res<'w, Subdivisions>,
+    pub base_shape: Res<'w, BaseShape>,
+    pub lod_settings: Res<'w, LodSettings>,
+    pub heightmap: Res<'w, Heightmap>,
+    pub normal_mode: Res<'w, NormalMode>,
+}
+
+//state of the sphere together with the mesh drawing it, for the systems replacing the mesh
+#[derive(SystemParam)]
+pub struct SphereMesh<'w> {
+    pub state: ResMut<'w, SphereState>,
+    pub geomorph: ResMut<'w, Geomorph>,
+    pub meshes: ResMut<'w, Assets<Mesh>>,
+}
+
+impl SphereMesh<'_> {
+    //replaces the mesh with one built from the current triangles of the sphere
+    pub fn rebuild(&mut self, heightmap: &Heightmap, normal_mode: NormalMode) {
+        let new_mesh = build_sphere_mesh(&self.state, &self.geomorph, heightmap, normal_mode);
+        if let Some(mesh) = self.meshes.get_mut(&self.state.mesh) {
+            *mesh = new_mesh;
+        }
+    }
+}
+
+impl Default for SphereState {
+    fn default() -> Self {
+        SphereState {
+            wireframe: false,
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+            indices: Vec::new(),
+            pieces: Vec::new(),
+            adjacency: TriangleAdjacency::default(),
+            cells: CellGrid::default(),
+            layers: TriangleLayers::default(),
+            locator: TriangleLocator::default(),
+            mesh: Handle::default(),
+            lod: LodTree::default(),
+            cube: QuadTree::default(),
+            subdivisions: 0,
+            base: Polyhedron::Icosahedron,
+            generation: 0,
+        }
+    }
+}
+
+impl SphereState {
+    //icosphere with every base face split depth times, without mesh or lod tree
+    pub fn uniform(depth: usize) -> Self {
+        let mut sphere_state = SphereState::default();
+        let subdivisions = Subdivisions { value: depth, ..default() };
+        sphere_state.build(BaseShape::default(), &subdivisions, &LodSettings { enabled: false, ..default() }, &[]);
+        sphere_state
+    }
+
+    //icosphere refined by the lod tree around the focus points, at least depth levels deep everywhere
+    pub fn lod(depth: usize, settings: &LodSettings, focus: &[Vec3]) -> Self {
+        let mut sphere_state = SphereState::default();
+        let subdivisions = Subdivisions { value: depth, ..default() };
+        sphere_state.build(BaseShape::default(), &subdivisions, &LodSettings { enabled: true, ..settings.clone() }, focus);
+        sphere_state
+    }
+
+    //replaces the triangles with a new sphere of the given shape, the lod and cube trees are refined around the focus points
+    //without a mesh, the layers are resampled onto the new triangles like for set_triangles
+    pub fn build(&mut self, base_shape: BaseShape, subdivisions: &Subdivisions, lod_settings: &LodSettings, focus: &[Vec3]) {
+        let base = match base_shape {
+            BaseShape::Polyhedron(polyhedron) => polyhedron,
+            BaseShape::CubeSphere { .. } => Polyhedron::Icosahedron,
+        };
+        let (mut vertices, mut triangles) = base.faces();
+        let locator;
+        let depth = subdivisions.depth();
+
+        if let BaseShape::CubeSphere { warp } = base_shape {
+            let mut tree = QuadTree::new(warp);
+            while tree.update(focus, depth, &quad_lod_settings(lod_settings), &mut Geomorph::instant()) {}
+            (vertices, triangles) = tree.leaves();
+            self.cube = tree;
+            locator = TriangleLocator::Cube;
+        }
+        else if lod_settings.enabled {
+            let mut tree = LodTree::new(triangles);
+            while tree.update(focus, depth, lod_settings, &mut Geomorph::instant()) {}
+            (vertices, triangles) = tree.leaves();
+            self.lod = tree;
+            locator = TriangleLocator::Lod;
+        }
+        else if subdivisions.mode == SubdivisionMode::Frequency {
+            locator = TriangleLocator::frequency(&triangles, subdivisions.frequency());
+            (vertices, triangles) = frequency_subdivide(vertices, triangles, subdivisions.frequency(), subdivisions.placement);
+        }
+        else {
+            locator = TriangleLocator::uniform(&triangles, depth);
+            (vertices, triangles) = uniform_subdivide(vertices, triangles, depth, subdivisions.placement);
+        }
+        self.subdivisions = depth;
+        self.base = base;
+        self.set_triangles(vertices, triangles, locator);
+    }
+
+    //replaces the triangles of the sphere and rebuilds their adjacency and cells, the layers are resampled onto the new triangles
+    //the locator has to describe how the triangles were generated
+    pub fn set_triangles(&mut self, vertices: Vec<Vec3>, triangles: Vec<Triangle>, locator: TriangleLocator) {
+        self.adjacency = TriangleAdjacency::new(vertices.len(), &triangles);
+        self.cells = CellGrid::new(&vertices, &triangles);
+        let (indices, pieces) = match locator {
+            //the quadtree fans its leaves around the t-junctions itself
+            TriangleLocator::Cube => (triangles.iter().map(|triangle| triangle.corners).collect(), (0..triangles.len()).map(|i| i..i + 1).collect()),
+            _ => stitch(&vertices, &triangles),
+        };
+        self.indices = indices.into_iter().flatten().collect();
+        self.pieces = pieces;
+        self.vertices = vertices;
+        let old = std::mem::replace(&mut self.triangles, triangles);
+        self.locator = locator;
+        self.generation += 1;
+
+        if !self.layers.is_empty() {
+            let resampling = Resampling::new(&old, self);
+            self.layers.resample(&resampling);
+        }
+    }
+
+    //adds a layer with the default value on every triangle, replacing the layer with the same name
+    pub fn add_layer<T: LayerValue>(&mut self, name: &str) -> &mut TriangleLayer<T> {
+        self.layers.insert(name, TriangleLayer::<T>::new(self.triangles.len()));
+        self.layers.get_mut(name).expect("the layer was just inserted")
+    }
+
+    //values of the shared vertices laid out like the vertices of the mesh
+    //flat shaded meshes have a copy of every vertex for each triangle using it
+    pub fn mesh_vertices<T: Copy>(&self, values: &[T], normal_mode: NormalMode) -> Vec<T> {
+        match normal_mode {
+            NormalMode::Flat => self.indices.iter().map(|&index| values[index as usize]).collect(),
+            NormalMode::Smooth => values.to_vec(),
+        }
+    }
+
+    //index of the triangle containing a point given in the local space of the sphere
+    pub fn locate(&self, point: Vec3) -> Option<usize> {
+        match &self.locator {
+            TriangleLocator::Empty => None,
+            TriangleLocator::Uniform { base, depth } => locate_uniform(base, *depth, point).map(|guess| walk(&self.triangles, &self.adjacency, guess, point)),
+            //the grid guess is exact for normalized placement and a step or two off otherwise
+            TriangleLocator::Frequency { base, frequency } => locate_frequency(base, *frequency, point).map(|guess| walk(&self.triangles, &self.adjacency, guess, point)),
+            TriangleLocator::Lod => self.lod.locate(point),
+            TriangleLocator::Cube => self.cube.locate(point),
+        }
+    }
+
+    //index of the cell containing a point given in the local space of the sphere, the same as its shared vertex
+    pub fn locate_cell(&self, point: Vec3) -> Option<usize> {
+        let triangle = self.locate(point)?;
+        Some(self.cells.cell_in_triangle(&self.triangles[triangle], point))
+    }
+
+    //point where the ray from the center along a direction meets the rendered mesh, and the normal of the facet there
+    //both in the local space of the sphere, the mesh includes the terrain and the vertices that are still morphing
+    pub fn rendered_surface(&self, direction: Vec3, geomorph: &Geomorph, heightmap: &Heightmap) -> Option<(Vec3, Vec3)> {
+        let direction = direction.normalize();
+        let triangle = self.locate(direction)?;
+        self.pieces
+            .get(triangle)?
+            .clone()
+            .filter_map(|piece| {
+                let [a, b, c] = [0, 1, 2].map(|i| geomorph.position(self.vertices[self.indices[piece * 3 + i] as usize], heightmap));
+                let normal = (b - a).cross(c - a).try_normalize()?;
+                let along = direction.dot(normal);
+                if along <= 0.0 {
+                    return None;
+                }
+                //the piece the ray passes through, or the closest one while vertices are moving
+                Some((containment(&Triangle3d::new(a, b, c), direction), direction * (a.dot(normal) / along), normal))
+            })
+            .max_by(|x, y| x.0.total_cmp(&y.0))
+            .map(|(_, point, normal)| (point, normal))
+    }
+}
+
+//spawns the initial sphere
+pub fn spawn_sphere(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut sphere: SphereMesh,
+    settings: SphereSettings,
+    character_state: Res<CharacterState>,
+) {
+    create_geodesic_sphere_tri(&mut commands, &mut materials, &mut sphere, &settings, &character_state);
+}
+
+//builds the sphere at the given subdivision level and spawns the entity rendering it
+pub fn create_geodesic_sphere_tri(
+    commands: &mut Commands,
+    materials: &mut Assets<StandardMaterial>,
+    sphere: &mut SphereMesh,
+    settings: &SphereSettings,
+    character_state: &CharacterState,
+) {
+    let SphereMesh { state: sphere_state, geomorph, meshes } = sphere;
+    //the new sphere appears at once, there is nothing to morph from
+    geomorph.vertices.clear();
+    //the tree is built down to the subdivision level, then refined around the character
+    //the camera is taken into account from the next frame on by update_lod
+    sphere_state.build(*settings.base_shape, &settings.subdivisions, &settings.lod_settings, &[character_state.center]);
+
+    //create one mesh with all triangles
+    let mesh = build_sphere_mesh(sphere_state, geomorph, &settings.heightmap, *settings.normal_mode);
+
+    let mesh_handle = meshes.add(mesh);
+    sphere_state.mesh = mesh_handle.clone();
+
+    commands.spawn((
+        PbrBundle {
+            mesh: mesh_handle,
+            material: materials.add(StandardMaterial {
+                base_color: Color::srgb(1.0, 1.0, 1.0),
+                ..Default::default()
+            }), 
+            ..Default::default()
+        }, 
+        Wireframe,
+        Sphere,
+    ));
+}
+
+//builds a single watertight indexed mesh from the shared vertices and triangles of the sphere state
+//the mesh has no colors yet, update_colors adds them after every system replacing the mesh
+pub fn build_sphere_mesh(sphere_state: &SphereState, geomorph: &Geomorph, heightmap: &Heightmap, normal_mode: NormalMode) -> Mesh {
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+
+    let positions: Vec<Vec3> = sphere_state.vertices.iter().map(|&vertex| geomorph.position(vertex, heightmap)).collect();
+    let indices = match normal_mode {
+        NormalMode::Flat => (0..sphere_state.indices.len() as u32).collect(),
+        NormalMode::Smooth => sphere_state.indices.clone(),
+    };
+
+    insert_positions(&mut mesh, sphere_state, positions, normal_mode);
+    mesh.insert_indices(Indices::U32(indices));
+    mesh
+}
+
+//writes the positions of the shared vertices into the mesh together with normals matching them
+pub fn insert_positions(mesh: &mut Mesh, sphere_state: &SphereState, positions: Vec<Vec3>, normal_mode: NormalMode) {
+    let normals = match normal_mode {
+        NormalMode::Flat => flat_normals(&positions, &sphere_state.indices),
+        NormalMode::Smooth => smooth_normals(&positions, &sphere_state.indices),
+    };
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, sphere_state.mesh_vertices(&positions, normal_mode));
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+}
+
+//writes one value per shared vertex into a vertex attribute of the mesh
+pub fn insert_vertex_values<T: Copy>(mesh: &mut Mesh, sphere_state: &SphereState, attribute: MeshVertexAttribute, values: &[T], normal_mode: NormalMode)
+where
+    Vec<T>: Into<VertexAttributeValues>,
+{
+    mesh.insert_attribute(attribute, sphere_state.mesh_vertices(values, normal_mode));
+}
+
+//moves the uniformly subdivided sphere to another subdivision level without popping
+//a finer level replaces the mesh straight away and its new vertices morph in from their parent edges
+//a coarser level only replaces the mesh once the vertices it drops have morphed out, see apply_geomorph
+pub fn morph_subdivisions(sphere: &mut SphereMesh, settings: &SphereSettings) {
+    let subdivisions = settings.subdivisions.depth();
+    let SphereMesh { state: sphere_state, geomorph, .. } = sphere;
+    let shown = sphere_state.subdivisions;
+    let mut levels = uniform_levels(sphere_state.base, shown.max(subdivisions));
+
+    //the midpoints created by subdividing level i are the vertices level i + 1 adds
+    for (level, (_, triangles)) in levels.iter().enumerate().take(shown.max(subdivisions)) {
+        for triangle in triangles {
+            let [a, b, c] = triangle.triangle.vertices;
+            for (p, q) in [(a, b), (b, c), (c, a)] {
+                let mid = edge_midpoint(p, q);
+                if level >= subdivisions {
+                    geomorph.morph_out(mid, p, q);
+                } else if level >= shown {
+                    geomorph.morph_in(mid, p, q);
+                } else {
+                    geomorph.cancel_morph_out(mid);
+                }
+            }
+        }
+    }
+
+    if subdivisions > shown {
+        let locator = TriangleLocator::uniform(&levels[0].1, subdivisions);
+        let (vertices, triangles) = levels.swap_remove(subdivisions);
+        sphere_state.set_triangles(vertices, triangles, locator);
+        sphere_state.subdivisions = subdivisions;
+        sphere.rebuild(&settings.heightmap, *settings.normal_mode);
+    }
+}
+
+//reacts to changes of Subdivisions, BaseShape, LodSettings, Heightmap and NormalMode made by the app
+//switching lod on or off, the base shape, the subdivision mode or the placement replaces the sphere
+//uniform midpoint spheres morph to new subdivisions, frequency and relaxed spheres are replaced, the lod and cube trees pick them up in update_lod
+pub fn apply_sphere_settings(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    sphere_query: Query<Entity, With<Sphere>>,
+    mut sphere: SphereMesh,
+    settings: SphereSettings,
+    character_state: Res<CharacterState>,
+    //lod mode, base shape, subdivision mode and placement the current sphere was built with
+    mut built: Local<Option<(bool, BaseShape, SubdivisionMode, Placement)>>,
+) {
+    let subdivisions = &settings.subdivisions;
+    let current = (settings.lod_settings.enabled, *settings.base_shape, subdivisions.mode, subdivisions.placement);
+    let old = *built.get_or_insert(current);
+    let replaced = match sphere.state.locator {
+        TriangleLocator::Frequency { .. } => true,
+        TriangleLocator::Uniform { .. } => matches!(subdivisions.placement, Placement::Relaxed { .. }),
+        _ => false,
+    };
+
+    if current != old || (subdivisions.is_changed() && !subdivisions.is_added() && replaced) {
+        for entity in sphere_query.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+        create_geodesic_sphere_tri(&mut commands, &mut materials, &mut sphere, &settings, &character_state);
+        *built = Some(current);
+        return;
+    }
+    if subdivisions.is_changed() && matches!(sphere.state.locator, TriangleLocator::Uniform { .. }) {
+        morph_subdivisions(&mut sphere, &settings);
+    }
+    //new terrain or shading only changes the vertices, the triangles stay the same
+    let (heightmap, normal_mode) = (&settings.heightmap, &settings.normal_mode);
+    if (heightmap.is_changed() && !heightmap.is_added()) || (normal_mode.is_changed() && !normal_mode.is_added()) {
+        sphere.rebuild(heightmap, **normal_mode);
+    }
+}
+
+//advances the geomorph and moves the mesh vertices along
+pub fn apply_geomorph(mut sphere: SphereMesh, settings: SphereSettings, time: Res<Time>) {
+    let moved = sphere.geomorph.advance(time.delta_seconds());
+    let depth = settings.subdivisions.depth();
+
+    //swap in the coarser uniform level once the vertices it drops sit on their parent edges
+    if matches!(sphere.state.locator, TriangleLocator::Uniform { .. }) && sphere.state.subdivisions > depth && !sphere.geomorph.is_morphing_out() {
+        let mut levels = uniform_levels(sphere.state.base, depth);
+        let locator = TriangleLocator::uniform(&levels[0].1, depth);
+        let (vertices, triangles) = levels.pop().unwrap();
+        sphere.state.set_triangles(vertices, triangles, locator);
+        sphere.state.subdivisions = depth;
+        sphere.geomorph.vertices.retain(|_, morph| morph.direction > 0.0);
+        sphere.rebuild(&settings.heightmap, *settings.normal_mode);
+        return;
+    }
+
+    if moved {
+        let SphereMesh { state: sphere_state, geomorph, meshes } = &mut sphere;
+        if let Some(mesh) = meshes.get_mut(&sphere_state.mesh) {
+            let positions: Vec<Vec3> = sphere_state.vertices.iter().map(|&vertex| geomorph.position(vertex, &settings.heightmap)).collect();
+            insert_positions(mesh, sphere_state, positions, *settings.normal_mode);
+        }
+    }
+}
+
+//refines the lod tree around the character and camera, rebuilding the mesh when the leaves change
+//the cube sphere always goes through its quadtree, without lod it is just kept at the subdivision level
+pub fn update_lod(
+    mut sphere: SphereMesh,
+    settings: SphereSettings,
+    character_state: Res<CharacterState>,
+    camera_query: Query<&Transform, With<LodCamera>>,
+) {
+    let cube = matches!(sphere.state.locator, TriangleLocator::Cube);
+    if !settings.lod_settings.enabled && !cube {
+        return;
+    }
+
+    //the sphere sits unrotated at the origin, so world space is its local space
+    let mut focus = vec![character_state.center];
+    focus.extend(camera_query.iter().map(|transform| transform.translation));
+
+    let depth = settings.subdivisions.depth();
+    let SphereMesh { state: sphere_state, geomorph, .. } = &mut sphere;
+    let changed = if cube {
+        sphere_state.cube.update(&focus, depth, &quad_lod_settings(&settings.lod_settings), geomorph)
+    } else {
+        sphere_state.lod.update(&focus, depth, &settings.lod_settings, geomorph)
+    };
+    if changed {
+        let (vertices, triangles) = if cube { sphere_state.cube.leaves() } else { sphere_state.lod.leaves() };
+        sphere_state.set_triangles(vertices, triangles, if cube { TriangleLocator::Cube } else { TriangleLocator::Lod });
+        sphere.rebuild(&settings.heightmap, *settings.normal_mode);
+    }
+}
+
+//settings for the cube sphere quadtree, without lod it never splits past the subdivision level
+fn quad_lod_settings(lod_settings: &LodSettings) -> LodSettings {
+    if lod_settings.enabled {
+        lod_settings.clone()
+    } else {
+        LodSettings {
+            max_depth: 0,
+            ..lod_settings.clone()
+        }
+    }
+}
\ No newline at end of file